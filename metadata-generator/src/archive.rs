@@ -0,0 +1,367 @@
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+use serde::{Deserialize, Serialize};
+use zip::{result::ZipError, write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+use crate::cancel::CancellationToken;
+use crate::error::{Error, IoResultExt, Result};
+use crate::file_analyzer::{media_kind, MediaKind};
+use crate::hasher::PROGRESS_STEP;
+use crate::events::{FileOutcome, ProgressEvent};
+use crate::metadata_generator::{read_metadata_file, Metadata, MetadataGenerator};
+
+/// Container formats for a packaged edition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarZst,
+}
+
+impl ArchiveFormat {
+    /// Picks the format from the file name: `.zip`, `.tar`, or `.tar.zst` / `.tzst`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+        if file_name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if file_name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if file_name.ends_with(".tar.zst") || file_name.ends_with(".tzst") {
+            Some(ArchiveFormat::TarZst)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether a verification base is an archive file rather than a folder
+pub fn is_archive(path: &Path) -> bool {
+    path.is_file() && ArchiveFormat::from_path(path).is_some()
+}
+
+/// Summary of a packaged edition
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackageSummary {
+    pub archive_path: PathBuf,
+    pub file_count: usize,
+    pub archive_size: u64,
+}
+
+/// A regular file found inside an archive, hashed while streaming
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub file_hash: String,
+    pub file_size: u64,
+}
+
+/// Everything read from an archive in a single pass
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveContents {
+    /// Entries keyed by their path inside the archive, using `/` separators
+    pub entries: BTreeMap<String, ArchiveEntry>,
+    /// Name and contents of the metadata JSON embedded in the archive, if any
+    pub metadata: Option<(String, Vec<u8>)>,
+}
+
+impl ArchiveContents {
+    /// Finds the entry for a recorded path such as `./art.png`; when the archive wraps the edition
+    /// in a top-level folder (e.g. `Edition/art.png`), a unique match below that folder is used
+    pub fn resolve(&self, path: &str) -> Option<(&String, &ArchiveEntry)> {
+        let path = relative_name(Path::new(&path.replace('\\', "/")))?;
+        if let Some(found) = self.entries.get_key_value(&path) {
+            return Some(found);
+        }
+        let suffix = format!("/{}", path);
+        let mut matches = self.entries.iter().filter(|(name, _)| name.ends_with(&suffix));
+        match (matches.next(), matches.next()) {
+            (Some(found), None) => Some(found),
+            _ => None,
+        }
+    }
+}
+
+fn unsupported_format(path: &Path) -> Error {
+    Error::validation(path, "Unsupported archive format (expected .zip, .tar or .tar.zst)")
+}
+
+/// Lists the files that make up an edition as (name relative to the edition folder, source path)
+/// The metadata JSON comes first so streaming readers meet the manifest before the files; a recorded
+/// path that is absolute or leaves the edition folder is rejected rather than copied
+pub(crate) fn edition_files(folder_path: &Path, metadata_path: &Path, metadata: &Metadata) -> Result<Vec<(String, PathBuf)>> {
+    let metadata_name = metadata_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let mut files = vec![(metadata_name, metadata_path.to_path_buf())];
+    let recorded_paths = metadata
+        .artwork_files
+        .iter()
+        .map(|artwork_file| &artwork_file.file_name)
+        .chain(&metadata.certificate_of_authenticity);
+    for recorded_path in recorded_paths {
+        let name = relative_name(Path::new(recorded_path)).ok_or_else(|| {
+            Error::validation(metadata_path, format!("Recorded path is absolute or leaves the edition folder: {}", recorded_path))
+        })?;
+        let source = folder_path.join(&name);
+        files.push((name, source));
+    }
+    Ok(files)
+}
+
+/// Streams the metadata JSON, artwork files and certificate into a ZIP, TAR or tar.zst archive, reporting
+/// each file to the generator's observer; a run cancelled through the generator removes the partial archive
+pub fn package_edition(
+    generator: &MetadataGenerator,
+    folder_path: &Path,
+    metadata_path: &Path,
+    output_path: &Path,
+) -> Result<PackageSummary> {
+    let format = ArchiveFormat::from_path(output_path).ok_or_else(|| unsupported_format(output_path))?;
+    let metadata = read_metadata_file(metadata_path)?;
+    let files = edition_files(folder_path, metadata_path, &metadata)?;
+    // Check the sources up front so a missing one is reported by name rather than as an archive error
+    for (_, source) in &files {
+        fs::metadata(source).with_path(source)?;
+    }
+
+    let output = BufWriter::new(File::create(output_path).with_path(output_path)?);
+    generator.emit(ProgressEvent::ScanStarted { total_files: files.len() });
+    if let Err(e) = write_package(generator, format, output, &files).with_path(output_path) {
+        if e.is_cancelled() {
+            let _ = fs::remove_file(output_path);
+        }
+        return Err(e);
+    }
+    generator.emit(ProgressEvent::Finished { processed_files: files.len(), failed_files: 0 });
+
+    Ok(PackageSummary {
+        archive_path: output_path.to_path_buf(),
+        file_count: files.len(),
+        archive_size: fs::metadata(output_path).with_path(output_path)?.len(),
+    })
+}
+
+fn write_package(
+    generator: &MetadataGenerator,
+    format: ArchiveFormat,
+    output: BufWriter<File>,
+    files: &[(String, PathBuf)],
+) -> io::Result<()> {
+    let total_files = files.len();
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = ZipWriter::new(output);
+            for (index, (name, source)) in files.iter().enumerate() {
+                // Images, video and audio are already compressed, so deflating them only costs time
+                let extension = Path::new(name).extension().unwrap_or_default().to_string_lossy().to_uppercase();
+                let compression = match media_kind(&extension) {
+                    MediaKind::Image | MediaKind::Video | MediaKind::Audio => CompressionMethod::Stored,
+                    _ => CompressionMethod::Deflated,
+                };
+                let options = SimpleFileOptions::default()
+                    .compression_method(compression)
+                    .large_file(fs::metadata(source)?.len() >= u32::MAX as u64);
+                zip.start_file(name.as_str(), options).map_err(zip_error)?;
+                generator.emit(ProgressEvent::FileStarted { file_name: name.clone(), index, total_files });
+                io::copy(&mut generator.progress_reader(source, name, index, total_files)?, &mut zip)?;
+                generator.emit(ProgressEvent::FileCompleted { file_name: name.clone(), index, total_files, outcome: FileOutcome::Hashed });
+            }
+            zip.finish().map_err(zip_error)?.flush()?;
+        }
+        ArchiveFormat::Tar => {
+            let mut builder = tar::Builder::new(output);
+            for (index, (name, source)) in files.iter().enumerate() {
+                append_tar_file(generator, &mut builder, name, source, index, total_files)?;
+            }
+            builder.into_inner()?.flush()?;
+        }
+        ArchiveFormat::TarZst => {
+            let mut builder = tar::Builder::new(zstd::Encoder::new(output, 0)?);
+            for (index, (name, source)) in files.iter().enumerate() {
+                append_tar_file(generator, &mut builder, name, source, index, total_files)?;
+            }
+            builder.into_inner()?.finish()?.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds a file to a tar archive like `append_path_with_name`, reading it through the generator's progress reader
+fn append_tar_file<W: Write>(
+    generator: &MetadataGenerator,
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    source: &Path,
+    index: usize,
+    total_files: usize,
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_metadata(&fs::metadata(source)?);
+    generator.emit(ProgressEvent::FileStarted { file_name: name.to_string(), index, total_files });
+    builder.append_data(&mut header, name, generator.progress_reader(source, name, index, total_files)?)?;
+    generator.emit(ProgressEvent::FileCompleted { file_name: name.to_string(), index, total_files, outcome: FileOutcome::Hashed });
+    Ok(())
+}
+
+/// Converts a ZIP error, keeping I/O errors as they are so a cancelled read stays recognisable
+fn zip_error(error: ZipError) -> io::Error {
+    match error {
+        ZipError::Io(error) => error,
+        error => io::Error::other(error),
+    }
+}
+
+/// Counts the bytes read from the archive file so progress can be reported as a fraction of its size,
+/// and stops reading once the pass is cancelled
+struct CountingReader<R> {
+    inner: R,
+    position: Rc<Cell<u64>>,
+    cancellation: CancellationToken,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.cancellation.check()?;
+        let read = self.inner.read(buffer)?;
+        self.position.set(self.position.get() + read as u64);
+        Ok(read)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let new_position = self.inner.seek(position)?;
+        self.position.set(new_position);
+        Ok(new_position)
+    }
+}
+
+/// Normalises a relative path to `a/b` form, or None for paths that could escape the folder or
+/// archive root they are joined to: absolute paths, `..` components and empty paths
+pub(crate) fn relative_name(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            std::path::Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    if parts.is_empty() { None } else { Some(parts.join("/")) }
+}
+
+/// The metadata JSON sits at the archive root, or inside a single top-level edition folder
+fn is_metadata_name(name: &str) -> bool {
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    name.matches('/').count() <= 1 && (file_name.ends_with("_metadata.json") || file_name == "metadata.json")
+}
+
+/// Hashes one entry with BLAKE3, keeping its bytes when it is the embedded metadata JSON
+/// `on_read` is called about every 1 MiB, so a large entry does not stall the progress display
+fn read_entry(name: String, reader: &mut dyn Read, contents: &mut ArchiveContents, on_read: &mut dyn FnMut()) -> io::Result<()> {
+    let mut hasher = blake3::Hasher::new();
+    let file_size = if contents.metadata.is_none() && is_metadata_name(&name) {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        hasher.update(&bytes);
+        let file_size = bytes.len() as u64;
+        contents.metadata = Some((name.clone(), bytes));
+        file_size
+    } else {
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut file_size = 0;
+        let mut bytes_reported = 0;
+        loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            hasher.update(&buffer[..read]);
+            file_size += read as u64;
+            if file_size - bytes_reported >= PROGRESS_STEP as u64 {
+                on_read();
+                bytes_reported = file_size;
+            }
+        }
+        file_size
+    };
+    contents.entries.insert(name, ArchiveEntry {
+        file_hash: hasher.finalize().to_hex().to_string(),
+        file_size,
+    });
+    Ok(())
+}
+
+fn read_tar<R: Read>(mut archive: tar::Archive<R>, contents: &mut ArchiveContents, progress: &mut dyn FnMut(&str)) -> io::Result<()> {
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let Some(name) = relative_name(&entry.path()?) else {
+            continue;
+        };
+        progress(&name);
+        read_entry(name, &mut entry, contents, &mut || progress(""))?;
+    }
+    Ok(())
+}
+
+/// Reads every regular file in a ZIP, TAR or tar.zst archive in one pass, hashing it in memory
+/// without extracting anything to disk; `progress` receives the bytes read so far and the archive size,
+/// with the entry name when an entry starts and an empty name while it is read and once the archive is done
+pub fn read_archive(
+    archive_path: &Path,
+    cancellation: &CancellationToken,
+    progress: &mut dyn FnMut(&str, u64, u64),
+) -> Result<ArchiveContents> {
+    let format = ArchiveFormat::from_path(archive_path).ok_or_else(|| unsupported_format(archive_path))?;
+    read_entries(format, archive_path, cancellation, progress).with_path(archive_path)
+}
+
+fn read_entries(
+    format: ArchiveFormat,
+    archive_path: &Path,
+    cancellation: &CancellationToken,
+    progress: &mut dyn FnMut(&str, u64, u64),
+) -> io::Result<ArchiveContents> {
+    let archive_size = fs::metadata(archive_path)?.len();
+    let position = Rc::new(Cell::new(0));
+    let reader = CountingReader {
+        inner: File::open(archive_path)?,
+        position: Rc::clone(&position),
+        cancellation: cancellation.clone(),
+    };
+    let bytes_read = || position.get().min(archive_size);
+    let mut contents = ArchiveContents::default();
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = ZipArchive::new(reader).map_err(zip_error)?;
+            for index in 0..zip.len() {
+                cancellation.check()?;
+                let mut file = zip.by_index(index).map_err(zip_error)?;
+                if !file.is_file() {
+                    continue;
+                }
+                let Some(name) = file.enclosed_name().as_deref().and_then(relative_name) else {
+                    continue;
+                };
+                progress(&name, bytes_read(), archive_size);
+                read_entry(name, &mut file, &mut contents, &mut || progress("", bytes_read(), archive_size))?;
+            }
+        }
+        ArchiveFormat::Tar => read_tar(tar::Archive::new(reader), &mut contents, &mut |name| progress(name, bytes_read(), archive_size))?,
+        ArchiveFormat::TarZst => {
+            let decoder = zstd::Decoder::new(reader)?;
+            read_tar(tar::Archive::new(decoder), &mut contents, &mut |name| progress(name, bytes_read(), archive_size))?
+        }
+    }
+
+    progress("", archive_size, archive_size);
+    Ok(contents)
+}
@@ -0,0 +1,358 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+use serde::{Deserialize, Serialize};
+use crate::constants::user_data_dir;
+use crate::error::{Error, IoResultExt, Result};
+use crate::time::utc_now;
+use crate::metadata_generator::{MetadataGenerator, VerificationReport, VerificationResult};
+
+/// `previous_hash` of the first record in a log
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Bytes read at a time when looking for the last record
+const TAIL_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// An edition registered for periodic fixity checks
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditTarget {
+    pub metadata_file: PathBuf,
+    /// Folder or archive holding the artwork files
+    pub base_folder: PathBuf,
+}
+
+/// The editions an audit run verifies, stored as JSON
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AuditRegistry {
+    #[serde(skip)]
+    path: PathBuf,
+    pub targets: Vec<AuditTarget>,
+}
+
+impl AuditRegistry {
+    /// Registry shared by every audit run of the current user
+    pub fn default_path() -> Option<PathBuf> {
+        user_data_dir().map(|data_dir| data_dir.join("audit_targets.json"))
+    }
+
+    /// Loads the registry, starting empty if the file does not exist yet
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut registry = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str::<Self>(&content).map_err(|e| Error::parse(path, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(Error::io(path, e)),
+        };
+        registry.path = path.to_path_buf();
+        Ok(registry)
+    }
+
+    /// Registers an edition by absolute path, so scheduled runs work from any directory
+    /// Returns false if it was already registered
+    pub fn add(&mut self, metadata_file: &Path, base_folder: &Path) -> Result<bool> {
+        let target = AuditTarget {
+            metadata_file: fs::canonicalize(metadata_file).with_path(metadata_file)?,
+            base_folder: fs::canonicalize(base_folder).with_path(base_folder)?,
+        };
+        if self.targets.contains(&target) {
+            return Ok(false);
+        }
+        self.targets.push(target);
+        Ok(true)
+    }
+
+    /// Unregisters every target of a metadata file; returns false if there was none
+    pub fn remove(&mut self, metadata_file: &Path) -> bool {
+        let metadata_file = fs::canonicalize(metadata_file).unwrap_or_else(|_| metadata_file.to_path_buf());
+        let count = self.targets.len();
+        self.targets.retain(|target| target.metadata_file != metadata_file);
+        self.targets.len() != count
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).with_path(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| Error::io(&self.path, e.into()))?;
+        fs::write(&self.path, content).with_path(&self.path)
+    }
+}
+
+/// Counts from a `VerificationReport`, kept with each audit record
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditSummary {
+    pub metadata_file_hash: String,
+    pub total_files: usize,
+    pub valid_files: usize,
+    pub invalid_files: usize,
+    pub certificate_valid: Option<bool>,
+    pub merkle_root_valid: Option<bool>,
+    pub overall_valid: bool,
+}
+
+impl From<&VerificationReport> for AuditSummary {
+    fn from(report: &VerificationReport) -> Self {
+        Self {
+            metadata_file_hash: report.metadata_file_hash.clone(),
+            total_files: report.total_files,
+            valid_files: report.valid_files,
+            invalid_files: report.invalid_files,
+            certificate_valid: report.certificate_valid,
+            merkle_root_valid: report.merkle_root_valid,
+            overall_valid: report.overall_valid,
+        }
+    }
+}
+
+/// One line of the audit log: the outcome of verifying one edition at one point in time
+/// `record_hash` covers every other field, including `previous_hash`, so editing, removing or
+/// reordering any line but the last breaks the chain; records cut off the end leave a valid chain, so
+/// keep the latest `record_hash` elsewhere to detect truncation
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditRecord {
+    /// Position in the log, starting at 1
+    pub sequence: u64,
+    /// UTC time as `YYYY-MM-DDTHH:MM:SSZ`
+    pub recorded_at: String,
+    pub tool_version: String,
+    pub metadata_file: PathBuf,
+    pub base_folder: PathBuf,
+    /// None when the edition could not be verified at all, e.g. the drive was not mounted
+    pub summary: Option<AuditSummary>,
+    pub error: Option<String>,
+    pub files: Vec<VerificationResult>,
+    pub previous_hash: String,
+    pub record_hash: String,
+}
+
+impl AuditRecord {
+    /// BLAKE3 of the record serialized with an empty `record_hash`
+    fn chain_hash(&self) -> String {
+        let unsealed = AuditRecord { record_hash: String::new(), ..self.clone() };
+        let content = serde_json::to_vec(&unsealed).unwrap_or_default();
+        blake3::hash(&content).to_hex().to_string()
+    }
+}
+
+/// One file's fixity as recorded by an audit run
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FixityEvent {
+    pub sequence: u64,
+    pub recorded_at: String,
+    pub metadata_file: PathBuf,
+    pub result: VerificationResult,
+}
+
+/// Append-only JSON Lines log of audit records, each chained to the one before
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    /// Log shared by every audit run of the current user
+    pub fn default_path() -> Option<PathBuf> {
+        user_data_dir().map(|data_dir| data_dir.join("audit_log.jsonl"))
+    }
+
+    pub fn open(path: &Path) -> Self {
+        Self { path: path.to_path_buf() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every record in the log, oldest first; a missing log has none
+    pub fn records(&self) -> Result<Vec<AuditRecord>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Error::io(&self.path, e)),
+        };
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(line_index, line)| {
+                serde_json::from_str(line).map_err(|e| Error::parse(&self.path, format!("line {}: {}", line_index + 1, e)))
+            })
+            .collect()
+    }
+
+    /// Checks every link of the chain and returns the sequence number of the first record that was
+    /// altered, inserted or follows a removed one, or None if the chain is intact
+    /// Records removed from the end cannot be detected from the log alone; compare `last_record`
+    /// with a copy of its hash kept elsewhere
+    pub fn verify_chain(&self) -> Result<Option<u64>> {
+        let mut previous_hash = GENESIS_HASH.to_string();
+        for (index, record) in self.records()?.iter().enumerate() {
+            let expected_sequence = index as u64 + 1;
+            if record.sequence != expected_sequence
+                || record.previous_hash != previous_hash
+                || record.record_hash != record.chain_hash()
+            {
+                return Ok(Some(expected_sequence));
+            }
+            previous_hash = record.record_hash.clone();
+        }
+        Ok(None)
+    }
+
+    /// The newest record, read from the end of the log; a missing or empty log has none
+    pub fn last_record(&self) -> Result<Option<AuditRecord>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::io(&self.path, e)),
+        };
+        let mut position = file.metadata().with_path(&self.path)?.len();
+        let mut tail = Vec::new();
+        loop {
+            let content = tail.trim_ascii_end();
+            let line_start = content.iter().rposition(|&byte| byte == b'\n').map(|index| index + 1);
+            if line_start.is_some() || position == 0 {
+                let line = &content[line_start.unwrap_or(0)..];
+                if line.is_empty() {
+                    return Ok(None);
+                }
+                return serde_json::from_slice(line)
+                    .map(Some)
+                    .map_err(|e| Error::parse(&self.path, format!("last line: {}", e)));
+            }
+            // Records carry every file's result, so read backwards in chunks until a line break
+            let chunk_size = position.min(TAIL_CHUNK_SIZE);
+            position -= chunk_size;
+            let mut chunk = vec![0; chunk_size as usize];
+            file.seek(SeekFrom::Start(position)).with_path(&self.path)?;
+            file.read_exact(&mut chunk).with_path(&self.path)?;
+            chunk.extend_from_slice(&tail);
+            tail = chunk;
+        }
+    }
+
+    /// Seals the outcome of verifying one edition and appends it to the log
+    pub fn append(&self, target: &AuditTarget, outcome: &Result<VerificationReport>) -> Result<AuditRecord> {
+        let last = self.last_record()?;
+        let (summary, error, files) = match outcome {
+            Ok(report) => (Some(AuditSummary::from(report)), None, report.results.clone()),
+            Err(e) => (None, Some(e.to_string()), Vec::new()),
+        };
+        let mut record = AuditRecord {
+            sequence: last.as_ref().map_or(1, |last| last.sequence + 1),
+            recorded_at: utc_now(),
+            tool_version: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            metadata_file: target.metadata_file.clone(),
+            base_folder: target.base_folder.clone(),
+            summary,
+            error,
+            files,
+            previous_hash: last.map_or_else(|| GENESIS_HASH.to_string(), |last| last.record_hash),
+            record_hash: String::new(),
+        };
+        record.record_hash = record.chain_hash();
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).with_path(parent)?;
+        }
+        let mut line = serde_json::to_string(&record).map_err(|e| Error::io(&self.path, e.into()))?;
+        line.push('\n');
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path).with_path(&self.path)?;
+        file.write_all(line.as_bytes()).with_path(&self.path)?;
+        file.sync_all().with_path(&self.path)?;
+        Ok(record)
+    }
+
+    /// Every recorded check of a file, oldest first
+    /// `file` is matched against the artwork file's full path, or its name as recorded in the metadata
+    pub fn file_history(&self, file: &Path) -> Result<Vec<FixityEvent>> {
+        let full_path = fs::canonicalize(file).ok();
+        let file_name = file.to_string_lossy();
+        let mut history = Vec::new();
+        for record in self.records()? {
+            for result in &record.files {
+                let matches = result.file_name == file_name
+                    || full_path.as_ref().is_some_and(|full_path| record.base_folder.join(&result.file_name) == *full_path);
+                if matches {
+                    history.push(FixityEvent {
+                        sequence: record.sequence,
+                        recorded_at: record.recorded_at.clone(),
+                        metadata_file: record.metadata_file.clone(),
+                        result: result.clone(),
+                    });
+                }
+            }
+        }
+        Ok(history)
+    }
+}
+
+/// Verifies every registered edition and appends one record per edition to the log
+/// An edition that cannot be verified is logged with its error and the run carries on; a cancelled
+/// run keeps the records already appended
+pub fn run_audit(generator: &MetadataGenerator, registry: &AuditRegistry, log: &AuditLog) -> Result<Vec<AuditRecord>> {
+    let mut records = Vec::new();
+    for target in &registry.targets {
+        let outcome = generator.verify_metadata_file_with_progress(&target.metadata_file, &target.base_folder);
+        if matches!(outcome, Err(Error::Cancelled)) {
+            return Err(Error::Cancelled);
+        }
+        records.push(log.append(target, &outcome)?);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A log with three records for missing editions, so no files need to be verified
+    fn log_with_records(name: &str) -> AuditLog {
+        let path = std::env::temp_dir().join(format!("audit-test-{}-{name}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let log = AuditLog::open(&path);
+        for index in 1..=3 {
+            let target = AuditTarget {
+                metadata_file: PathBuf::from(format!("edition_{index}.json")),
+                base_folder: PathBuf::from("art"),
+            };
+            log.append(&target, &Err(Error::validation(&target.metadata_file, "not mounted"))).unwrap();
+        }
+        log
+    }
+
+    fn rewrite_lines(log: &AuditLog, edit: impl FnOnce(&mut Vec<String>)) {
+        let content = fs::read_to_string(log.path()).unwrap();
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        edit(&mut lines);
+        fs::write(log.path(), lines.join("\n") + "\n").unwrap();
+    }
+
+    #[test]
+    fn appended_records_form_a_chain() {
+        let log = log_with_records("intact");
+        let records = log.records().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].previous_hash, GENESIS_HASH);
+        assert_eq!(records[2].previous_hash, records[1].record_hash);
+        assert_eq!(log.last_record().unwrap().unwrap().sequence, 3);
+        assert_eq!(log.verify_chain().unwrap(), None);
+        fs::remove_file(log.path()).unwrap();
+    }
+
+    #[test]
+    fn edited_record_breaks_the_chain() {
+        let log = log_with_records("edited");
+        rewrite_lines(&log, |lines| lines[1] = lines[1].replace("edition_2.json", "edition_9.json"));
+        assert_eq!(log.verify_chain().unwrap(), Some(2));
+        fs::remove_file(log.path()).unwrap();
+    }
+
+    #[test]
+    fn reordered_record_breaks_the_chain() {
+        let log = log_with_records("reordered");
+        rewrite_lines(&log, |lines| lines.swap(1, 2));
+        assert_eq!(log.verify_chain().unwrap(), Some(2));
+        fs::remove_file(log.path()).unwrap();
+    }
+}
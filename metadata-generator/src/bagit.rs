@@ -0,0 +1,409 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+use serde::{Deserialize, Serialize};
+use crate::archive::{edition_files, relative_name};
+use crate::checksums::ChecksumAlgorithm;
+use crate::error::{Error, IoResultExt, Result};
+use crate::events::{FileOutcome, ProgressEvent};
+use crate::hasher::UnifiedHasher;
+use crate::time::utc_today;
+use crate::metadata_generator::{read_metadata_file, Metadata, MetadataGenerator, VerificationReport, VerificationResult};
+
+const BAGIT_VERSION: &str = "1.0";
+
+/// Summary of a BagIt export
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BagExport {
+    pub bag_path: PathBuf,
+    pub file_count: usize,
+    pub payload_size: u64,
+}
+
+/// Hashes a file with an algorithm named as in `manifest-<algorithm>.txt`
+fn hash_with_algorithm(algorithm: &str, path: &Path, hasher: &UnifiedHasher) -> Result<String> {
+    let algorithm = ChecksumAlgorithm::from_name(algorithm)
+        .ok_or_else(|| Error::validation(path, format!("Unsupported BagIt checksum algorithm: {}", algorithm)))?;
+    algorithm.hash_file(path, hasher)
+}
+
+/// Percent-encodes the characters RFC 8493 does not allow in manifest paths
+fn encode_manifest_path(path: &str) -> String {
+    path.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+fn decode_manifest_path(path: &str) -> String {
+    path.replace("%0D", "\r")
+        .replace("%0d", "\r")
+        .replace("%0A", "\n")
+        .replace("%0a", "\n")
+        .replace("%25", "%")
+}
+
+/// Lists every file below a folder as paths relative to `root`, using `/` separators
+fn list_files(root: &Path, folder: &Path, files: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(folder).with_path(folder)? {
+        let path = entry.with_path(folder)?.path();
+        if path.is_dir() {
+            list_files(root, &path, files)?;
+        } else if path.is_file() {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let components: Vec<String> = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect();
+            files.push(components.join("/"));
+        }
+    }
+    Ok(())
+}
+
+fn write_manifest(bag_path: &Path, manifest_name: &str, entries: &[(String, String)]) -> Result<()> {
+    let manifest: String = entries
+        .iter()
+        .map(|(path, hash)| format!("{}  {}\n", hash, encode_manifest_path(path)))
+        .collect();
+    let manifest_path = bag_path.join(manifest_name);
+    fs::write(&manifest_path, manifest).with_path(&manifest_path)
+}
+
+/// Wraps long bag-info values onto indented continuation lines as RFC 8493 recommends
+fn bag_info_line(label: &str, value: &str) -> String {
+    let value = value.replace(['\r', '\n'], " ");
+    let mut line = format!("{}: ", label);
+    let mut width = line.len();
+    for (index, word) in value.split_whitespace().enumerate() {
+        if index > 0 {
+            if width + word.len() + 1 > 79 {
+                line.push_str("\n    ");
+                width = 4;
+            } else {
+                line.push(' ');
+                width += 1;
+            }
+        }
+        line.push_str(word);
+        width += word.len();
+    }
+    line.push('\n');
+    line
+}
+
+fn bag_info(metadata: &Metadata, payload_size: u64, file_count: usize) -> String {
+    let mut info = String::new();
+    if !metadata.gallery.is_empty() {
+        info.push_str(&bag_info_line("Source-Organization", &metadata.gallery));
+    }
+    info.push_str(&bag_info_line("Bagging-Date", &utc_today()));
+    info.push_str(&bag_info_line("External-Identifier", &metadata.artwork_id));
+    if !metadata.short_description.is_empty() {
+        info.push_str(&bag_info_line("External-Description", &metadata.short_description));
+    }
+    info.push_str(&bag_info_line("Payload-Oxum", &format!("{}.{}", payload_size, file_count)));
+    info.push_str(&bag_info_line("Bag-Size", &format!("{} bytes", payload_size)));
+    info.push_str(&bag_info_line("Title", &metadata.artwork_title));
+    info.push_str(&bag_info_line("Creator", &metadata.artwork_creator));
+    info.push_str(&bag_info_line("Year-Of-Creation", &metadata.year_of_creation.to_string()));
+    info.push_str(&bag_info_line(
+        "Edition",
+        &format!("{} of {}", metadata.edition_number, metadata.total_editions),
+    ));
+    if !metadata.issue_date.is_empty() {
+        info.push_str(&bag_info_line("Issue-Date", &metadata.issue_date));
+    }
+    if !metadata.keywords.is_empty() {
+        info.push_str(&bag_info_line("Keywords", &metadata.keywords.join(", ")));
+    }
+    if let Some(merkle_root) = &metadata.merkle_root {
+        info.push_str(&bag_info_line("Edition-Merkle-Root", merkle_root));
+    }
+    info
+}
+
+/// Lays out the edition as a BagIt 1.0 bag: the artwork files, certificate and metadata JSON
+/// are copied into `data/` and listed in `manifest-sha256.txt`, with bag-info.txt filled from the metadata
+/// Each payload file is reported to the generator's observer; an export cancelled through the generator
+/// removes what it had written, leaving the bag folder empty
+pub fn export_bag(
+    generator: &MetadataGenerator,
+    folder_path: &Path,
+    metadata_path: &Path,
+    bag_path: &Path,
+) -> Result<BagExport> {
+    let metadata = read_metadata_file(metadata_path)?;
+    // Keep the same layout inside data/ so the metadata verifies against it unchanged
+    let payload = edition_files(folder_path, metadata_path, &metadata)?;
+
+    if bag_path.exists() && fs::read_dir(bag_path).with_path(bag_path)?.next().is_some() {
+        return Err(Error::io(
+            bag_path,
+            std::io::Error::new(std::io::ErrorKind::AlreadyExists, "Bag folder is not empty"),
+        ));
+    }
+    let result = write_bag(generator, &metadata, &payload, bag_path);
+    if matches!(result, Err(Error::Cancelled)) {
+        let _ = fs::remove_dir_all(bag_path).and_then(|_| fs::create_dir(bag_path));
+    }
+    result
+}
+
+fn write_bag(
+    generator: &MetadataGenerator,
+    metadata: &Metadata,
+    payload: &[(String, PathBuf)],
+    bag_path: &Path,
+) -> Result<BagExport> {
+    let data_path = bag_path.join("data");
+    fs::create_dir_all(&data_path).with_path(&data_path)?;
+
+    let mut manifest = Vec::new();
+    let mut payload_size = 0;
+    let total_files = payload.len();
+    generator.emit(ProgressEvent::ScanStarted { total_files });
+    for (index, (relative_path, source)) in payload.iter().enumerate() {
+        let destination = data_path.join(relative_path);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).with_path(parent)?;
+        }
+        generator.emit(ProgressEvent::FileStarted { file_name: relative_path.clone(), index, total_files });
+        let mut reader = generator.progress_reader(source, relative_path, index, total_files).with_path(source)?;
+        let mut writer = File::create(&destination).with_path(&destination)?;
+        payload_size += io::copy(&mut reader, &mut writer).with_path(&destination)?;
+
+        // Hash the copy, so the manifest describes what actually landed in the bag
+        let hash = generator.hash_with_retries(ChecksumAlgorithm::Sha256, &destination, relative_path, index, total_files)?;
+        manifest.push((format!("data/{}", relative_path), hash));
+        generator.emit(ProgressEvent::FileCompleted {
+            file_name: relative_path.clone(),
+            index,
+            total_files,
+            outcome: FileOutcome::Hashed,
+        });
+    }
+    manifest.sort();
+    write_manifest(bag_path, "manifest-sha256.txt", &manifest)?;
+
+    let declaration_path = bag_path.join("bagit.txt");
+    fs::write(
+        &declaration_path,
+        format!("BagIt-Version: {}\nTag-File-Character-Encoding: UTF-8\n", BAGIT_VERSION),
+    )
+    .with_path(&declaration_path)?;
+    let bag_info_path = bag_path.join("bag-info.txt");
+    fs::write(&bag_info_path, bag_info(metadata, payload_size, payload.len())).with_path(&bag_info_path)?;
+
+    let mut tag_manifest = Vec::new();
+    for tag_file in ["bag-info.txt", "bagit.txt", "manifest-sha256.txt"] {
+        tag_manifest.push((tag_file.to_string(), hash_with_algorithm("sha256", &bag_path.join(tag_file), generator.hasher())?));
+    }
+    write_manifest(bag_path, "tagmanifest-sha256.txt", &tag_manifest)?;
+    generator.emit(ProgressEvent::Finished { processed_files: total_files, failed_files: 0 });
+
+    Ok(BagExport {
+        bag_path: bag_path.to_path_buf(),
+        file_count: payload.len(),
+        payload_size,
+    })
+}
+
+/// Parses a manifest into (path, checksum) pairs
+fn read_manifest(manifest_path: &Path) -> Result<Vec<(String, String)>> {
+    let content = fs::read_to_string(manifest_path).with_path(manifest_path)?;
+    let mut entries = Vec::new();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let (hash, path) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| Error::parse(manifest_path, format!("Malformed line: {}", line)))?;
+        entries.push((decode_manifest_path(path.trim_start()), hash.to_lowercase()));
+    }
+    Ok(entries)
+}
+
+/// Reads bag-info.txt into label/value pairs, joining continuation lines
+fn read_bag_info(bag_path: &Path) -> BTreeMap<String, String> {
+    let mut info: BTreeMap<String, String> = BTreeMap::new();
+    let content = fs::read_to_string(bag_path.join("bag-info.txt")).unwrap_or_default();
+    let mut last_label: Option<String> = None;
+    for line in content.lines() {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(label) = &last_label {
+                if let Some(value) = info.get_mut(label) {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            }
+        } else if let Some((label, value)) = line.split_once(':') {
+            let label = label.trim().to_string();
+            info.insert(label.clone(), value.trim().to_string());
+            last_label = Some(label);
+        }
+    }
+    info
+}
+
+/// Finds manifests with the given prefix (`manifest-` or `tagmanifest-`) and returns (algorithm, path) pairs
+fn find_manifests(bag_path: &Path, prefix: &str) -> Result<Vec<(String, PathBuf)>> {
+    let mut manifests = Vec::new();
+    for entry in fs::read_dir(bag_path).with_path(bag_path)? {
+        let path = entry.with_path(bag_path)?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if let Some(algorithm) = file_name.strip_prefix(prefix).and_then(|rest| rest.strip_suffix(".txt")) {
+            manifests.push((algorithm.to_lowercase(), path));
+        }
+    }
+    manifests.sort();
+    Ok(manifests)
+}
+
+/// Checks every manifest entry; a file that cannot be read fails on its own, cancellation stops the pass
+fn check_manifest(
+    bag_path: &Path,
+    algorithm: &str,
+    entries: &[(String, String)],
+    hasher: &UnifiedHasher,
+) -> Result<Vec<VerificationResult>> {
+    let mut results = Vec::new();
+    for (path, expected_hash) in entries {
+        let file_path = bag_path.join(path);
+        let (actual_hash, error) = if relative_name(Path::new(path)).is_none() {
+            (String::new(), Some("Path is absolute or leaves the bag".to_string()))
+        } else if !file_path.is_file() {
+            (String::new(), Some("File not found".to_string()))
+        } else {
+            match hash_with_algorithm(algorithm, &file_path, hasher) {
+                Ok(actual_hash) => (actual_hash, None),
+                Err(Error::Cancelled) => return Err(Error::Cancelled),
+                Err(e) => (String::new(), Some(e.message())),
+            }
+        };
+        results.push(VerificationResult {
+            file_name: path.clone(),
+            expected_hash: format!("{}:{}", algorithm, expected_hash),
+            is_valid: error.is_none() && actual_hash == *expected_hash,
+            actual_hash: if actual_hash.is_empty() { actual_hash } else { format!("{}:{}", algorithm, actual_hash) },
+            error,
+        });
+    }
+    Ok(results)
+}
+
+fn bag_error(file_name: &str, error: String) -> VerificationResult {
+    VerificationResult {
+        file_name: file_name.to_string(),
+        expected_hash: String::new(),
+        actual_hash: String::new(),
+        is_valid: false,
+        error: Some(error),
+    }
+}
+
+/// Validates an existing bag: every payload and tag manifest checksum, payload completeness and
+/// Payload-Oxum. If the payload contains an edition metadata file, its BLAKE3 hashes, certificate
+/// and Merkle root are verified too and merged into the same report, one result per file
+pub fn validate_bag(generator: &MetadataGenerator, bag_path: &Path) -> Result<VerificationReport> {
+    let bagit_declaration = fs::read_to_string(bag_path.join("bagit.txt"))
+        .map_err(|e| Error::validation(bag_path, format!("Not a BagIt bag, cannot read bagit.txt: {}", e)))?;
+    if !bagit_declaration.lines().any(|line| line.starts_with("BagIt-Version:")) {
+        return Err(Error::validation(bag_path, "bagit.txt does not declare a BagIt-Version"));
+    }
+
+    let payload_manifests = find_manifests(bag_path, "manifest-")?;
+    if payload_manifests.is_empty() {
+        return Err(Error::validation(bag_path, "Bag has no payload manifest"));
+    }
+
+    let data_path = bag_path.join("data");
+    let mut payload_files = Vec::new();
+    if data_path.is_dir() {
+        list_files(bag_path, &data_path, &mut payload_files)?;
+    }
+    payload_files.sort();
+
+    // The edition metadata in the payload drives the BLAKE3 verification; without one,
+    // the payload manifest stands in as the document being verified
+    let metadata_path = payload_files
+        .iter()
+        .find(|path| path.matches('/').count() == 1 && path.ends_with("_metadata.json"))
+        .map(|path| bag_path.join(path));
+    let mut report = match &metadata_path {
+        // A checkpoint would be written into the payload and break the bag's completeness
+        Some(metadata_path) => generator.verify_with_checkpoint(metadata_path, &data_path, false)?,
+        None => VerificationReport {
+            metadata_file_hash: generator.fingerprint_metadata_file(&payload_manifests[0].1)?,
+            total_files: 0,
+            valid_files: 0,
+            invalid_files: 0,
+            results: Vec::new(),
+            metadata_file_valid: true,
+            certificate_valid: None,
+            certificate_hash: None,
+            merkle_root_valid: None,
+            overall_valid: true,
+        },
+    };
+    // Metadata results are named relative to data/, bag results relative to the bag root
+    for result in &mut report.results {
+        result.file_name = format!("data/{}", result.file_name);
+    }
+
+    let mut bag_results = Vec::new();
+    for (algorithm, manifest_path) in &payload_manifests {
+        let manifest_name = manifest_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let entries = read_manifest(manifest_path)?;
+        bag_results.extend(check_manifest(bag_path, algorithm, &entries, generator.hasher())?);
+
+        // Every payload file must be listed in every payload manifest
+        let listed: HashSet<&String> = entries.iter().map(|(path, _)| path).collect();
+        for payload_file in &payload_files {
+            if !listed.contains(payload_file) {
+                bag_results.push(bag_error(payload_file, format!("Not listed in {}", manifest_name)));
+            }
+        }
+    }
+
+    for (algorithm, manifest_path) in find_manifests(bag_path, "tagmanifest-")? {
+        bag_results.extend(check_manifest(bag_path, &algorithm, &read_manifest(&manifest_path)?, generator.hasher())?);
+    }
+
+    if let Some(payload_oxum) = read_bag_info(bag_path).get("Payload-Oxum") {
+        let payload_size: u64 = payload_files
+            .iter()
+            .map(|path| bag_path.join(path).metadata().map(|m| m.len()).unwrap_or(0))
+            .sum();
+        let actual_oxum = format!("{}.{}", payload_size, payload_files.len());
+        if *payload_oxum != actual_oxum {
+            bag_results.push(bag_error(
+                "bag-info.txt",
+                format!("Payload-Oxum mismatch: expected {}, found {}", payload_oxum, actual_oxum),
+            ));
+        }
+    }
+
+    // A payload file is checked against both the edition metadata and the manifests; report it once,
+    // keeping the failing check when they disagree
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut results: Vec<VerificationResult> = Vec::new();
+    for result in report.results.drain(..).chain(bag_results) {
+        match positions.get(&result.file_name) {
+            Some(&position) => {
+                if results[position].is_valid && !result.is_valid {
+                    results[position] = result;
+                }
+            }
+            None => {
+                positions.insert(result.file_name.clone(), results.len());
+                results.push(result);
+            }
+        }
+    }
+    report.invalid_files = results.iter().filter(|result| !result.is_valid).count();
+    report.valid_files = results.len() - report.invalid_files;
+    report.total_files = results.len();
+    report.overall_valid = report.overall_valid && report.invalid_files == 0;
+    report.results = results;
+
+    Ok(report)
+}
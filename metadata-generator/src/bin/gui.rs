@@ -1,20 +1,15 @@
 use eframe::{egui, App, Frame};
 use rfd::FileDialog;
+use std::collections::BTreeSet;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::thread;
 
-
-#[path = "../constants.rs"]
-mod constants;
-#[path = "../hasher.rs"]
-mod hasher;
-#[path = "../metadata_generator.rs"]
-mod metadata_generator;
-#[path = "../file_analyzer.rs"]
-mod file_analyzer;
-
-use metadata_generator::{MetadataGenerator, Metadata, ProgressCallback, VerificationReport};
+use metadata_generator::{
+    archive, bagit, catalogue, certificate_qr, checksums, constants, diff, erc721, events, hash_cache, jsonld, report, tzip21, xmp,
+};
+use metadata_generator::error::IoResultExt;
+use metadata_generator::{CancellationToken, Metadata, MetadataGenerator, ProgressEvent, UpdateSummary, VerificationReport};
 
 #[derive(Clone)]
 enum GenerationState {
@@ -26,7 +21,9 @@ enum GenerationState {
         total_files: usize, 
         processed_files: usize 
     },
-    Complete { output_path: PathBuf },
+    Complete { output_path: PathBuf, update: Option<UpdateSummary> },
+    /// Stopped by the user before anything was written
+    Cancelled,
     Error { message: String },
 }
 
@@ -41,13 +38,214 @@ enum VerificationState {
         processed_files: usize 
     },
     Complete { report: VerificationReport },
+    Cancelled,
+    Error { message: String },
+}
+
+#[derive(Clone)]
+enum ExportState {
+    Idle,
+    Processing {
+        current_file: String,
+        file_progress: f32,
+        overall_progress: f32,
+        total_files: usize,
+        processed_files: usize,
+    },
+    Complete { output_path: PathBuf },
+    /// Stopped by the user; a partial archive or bag is removed
+    Cancelled,
+    Error { message: String },
+}
+
+/// Folder scan on the Catalogue tab
+#[derive(Clone)]
+enum CatalogueScanState {
+    Idle,
+    Scanning { folder: PathBuf },
+    /// Finished; the status is shown and the results refreshed on the next frame
+    Complete { status: String },
+}
+
+/// Folder watch on the Verify tab
+#[derive(Clone)]
+enum WatchState {
+    Idle,
+    Active(WatchStatus),
     Error { message: String },
 }
 
+#[derive(Clone, Default)]
+struct WatchStatus {
+    /// False while the folder is checked once before watching starts
+    watching: bool,
+    total_files: usize,
+    /// Files that currently do not match their recorded hash
+    changed_files: BTreeSet<String>,
+    /// Change notices, oldest first
+    alerts: Vec<String>,
+}
+
 #[derive(PartialEq)]
 enum Tab {
     Generate,
     Verify,
+    Export,
+    Compare,
+    Catalogue,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum CatalogueField {
+    Any,
+    Title,
+    Creator,
+    Keyword,
+    Gallery,
+    FileHash,
+}
+
+impl CatalogueField {
+    const ALL: [CatalogueField; 6] = [
+        CatalogueField::Any,
+        CatalogueField::Title,
+        CatalogueField::Creator,
+        CatalogueField::Keyword,
+        CatalogueField::Gallery,
+        CatalogueField::FileHash,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            CatalogueField::Any => "Any field",
+            CatalogueField::Title => "Title",
+            CatalogueField::Creator => "Creator",
+            CatalogueField::Keyword => "Keyword",
+            CatalogueField::Gallery => "Gallery",
+            CatalogueField::FileHash => "File hash",
+        }
+    }
+
+    fn query(&self, text: &str) -> catalogue::CatalogueQuery {
+        let text = Some(text.trim().to_string()).filter(|text| !text.is_empty());
+        let mut query = catalogue::CatalogueQuery::default();
+        match self {
+            CatalogueField::Any => query.text = text,
+            CatalogueField::Title => query.title = text,
+            CatalogueField::Creator => query.creator = text,
+            CatalogueField::Keyword => query.keyword = text,
+            CatalogueField::Gallery => query.gallery = text,
+            CatalogueField::FileHash => query.file_hash = text,
+        }
+        query
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum ExportFormat {
+    Erc721,
+    Tzip21,
+    JsonLd,
+    XmpSidecars,
+    DublinCore,
+    BagIt,
+    Checksums,
+    Archive,
+}
+
+impl ExportFormat {
+    fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Erc721 => "ERC-721 / OpenSea token metadata",
+            ExportFormat::Tzip21 => "Tezos TZIP-21 token metadata",
+            ExportFormat::JsonLd => "schema.org VisualArtwork JSON-LD",
+            ExportFormat::XmpSidecars => "XMP sidecars (one per artwork file)",
+            ExportFormat::DublinCore => "Dublin Core XML record",
+            ExportFormat::BagIt => "BagIt bag (RFC 8493)",
+            ExportFormat::Checksums => "Checksum manifest (sha256sum / md5sum)",
+            ExportFormat::Archive => "Edition archive (ZIP / tar.zst)",
+        }
+    }
+
+    fn default_file_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Erc721 => "token_metadata.json",
+            ExportFormat::Tzip21 => "tzip21_metadata.json",
+            ExportFormat::JsonLd => "artwork.jsonld",
+            ExportFormat::XmpSidecars => "",
+            ExportFormat::DublinCore => "dublin_core.xml",
+            ExportFormat::BagIt => "",
+            ExportFormat::Checksums => "SHA256SUMS",
+            ExportFormat::Archive => "edition.zip",
+        }
+    }
+}
+
+/// Export tab settings captured when Export is clicked, so the export can run on a worker thread
+struct ExportJob {
+    format: ExportFormat,
+    metadata_path: PathBuf,
+    uri_base: Option<String>,
+    external_url: Option<String>,
+    rights: Option<String>,
+    checksum_algorithm: checksums::ChecksumAlgorithm,
+    bsd_style: bool,
+}
+
+impl ExportJob {
+    fn run(&self, generator: &MetadataGenerator, output: &std::path::Path) -> metadata_generator::Result<()> {
+        let metadata_path = &self.metadata_path;
+        let metadata = metadata_generator::read_metadata_file(metadata_path)?;
+
+        match self.format {
+            ExportFormat::Erc721 => {
+                let options = erc721::Erc721Options {
+                    uri_base: self.uri_base.clone(),
+                    external_url: self.external_url.clone(),
+                };
+                let token = erc721::to_erc721(&metadata, &options);
+                std::fs::write(output, serde_json::to_string_pretty(&token)?).with_path(output)
+            }
+            ExportFormat::Tzip21 => {
+                let options = tzip21::Tzip21Options {
+                    uri_base: self.uri_base.clone(),
+                    base_folder: metadata_path.parent().map(|parent| parent.to_path_buf()),
+                    creators: Vec::new(),
+                    rights: self.rights.clone(),
+                };
+                let token = tzip21::to_tzip21(&metadata, &options);
+                std::fs::write(output, serde_json::to_string_pretty(&token)?).with_path(output)
+            }
+            ExportFormat::JsonLd => {
+                let options = jsonld::JsonLdOptions {
+                    uri_base: self.uri_base.clone(),
+                    base_folder: metadata_path.parent().map(|parent| parent.to_path_buf()),
+                    page_url: self.external_url.clone(),
+                };
+                let record = jsonld::to_json_ld(&metadata, &options)?;
+                std::fs::write(output, jsonld::render(&record, output)?).with_path(output)
+            }
+            ExportFormat::XmpSidecars => {
+                xmp::write_xmp_sidecars(&metadata, output, self.rights.as_deref()).map(|_| ())
+            }
+            ExportFormat::DublinCore => {
+                std::fs::write(output, xmp::dublin_core_record(&metadata, self.rights.as_deref())).with_path(output)
+            }
+            ExportFormat::BagIt => {
+                let folder_path = metadata_path.parent().unwrap_or(std::path::Path::new("."));
+                bagit::export_bag(generator, folder_path, metadata_path, output).map(|_| ())
+            }
+            ExportFormat::Archive => {
+                let folder_path = metadata_path.parent().unwrap_or(std::path::Path::new("."));
+                archive::package_edition(generator, folder_path, metadata_path, output).map(|_| ())
+            }
+            ExportFormat::Checksums => {
+                let folder_path = metadata_path.parent().unwrap_or(std::path::Path::new("."));
+                let style = if self.bsd_style { checksums::ManifestStyle::Bsd } else { checksums::ManifestStyle::Gnu };
+                checksums::write_manifest(generator, &metadata, folder_path, self.checksum_algorithm, style, output).map(|_| ())
+            }
+        }
+    }
 }
 
 struct GuiApp {
@@ -69,14 +267,49 @@ struct GuiApp {
     gallery: String,
     keywords: String,
     medium: String,
+    compute_ipfs_cid: bool,
+    use_hash_cache: bool,
+    rehash: bool,
     status: String,
     certificate_warning: String,
     generation_state: Arc<Mutex<GenerationState>>,
+    generation_cancel: CancellationToken,
     
     // Verify tab fields
     metadata_file: Option<PathBuf>,
     base_folder: Option<PathBuf>,
     verification_state: Arc<Mutex<VerificationState>>,
+    verification_cancel: CancellationToken,
+    resume_verification: bool,
+    watch_state: Arc<Mutex<WatchState>>,
+    watch_cancel: CancellationToken,
+    qr_payload: String,
+    qr_status: String,
+    report_status: String,
+
+    // Export tab fields
+    export_metadata_file: Option<PathBuf>,
+    export_format: ExportFormat,
+    export_uri_base: String,
+    export_external_url: String,
+    export_rights: String,
+    export_checksum_algorithm: checksums::ChecksumAlgorithm,
+    export_bsd_style: bool,
+    export_state: Arc<Mutex<ExportState>>,
+    export_cancel: CancellationToken,
+
+    // Compare tab fields
+    compare_old_file: Option<PathBuf>,
+    compare_new_file: Option<PathBuf>,
+    compare_diff: Option<diff::MetadataDiff>,
+    compare_status: String,
+
+    // Catalogue tab fields
+    catalogue_search: String,
+    catalogue_field: CatalogueField,
+    catalogue_results: Vec<catalogue::CatalogueEntry>,
+    catalogue_status: String,
+    catalogue_scan_state: Arc<Mutex<CatalogueScanState>>,
 }
 
 impl Default for GuiApp {
@@ -97,12 +330,41 @@ impl Default for GuiApp {
             gallery: String::new(),
             keywords: String::new(),
             medium: String::new(),
+            compute_ipfs_cid: false,
+            use_hash_cache: true,
+            rehash: false,
             status: String::new(),
             certificate_warning: String::new(),
             generation_state: Arc::new(Mutex::new(GenerationState::Idle)),
+            generation_cancel: CancellationToken::new(),
             metadata_file: None,
             base_folder: None,
             verification_state: Arc::new(Mutex::new(VerificationState::Idle)),
+            verification_cancel: CancellationToken::new(),
+            resume_verification: false,
+            watch_state: Arc::new(Mutex::new(WatchState::Idle)),
+            watch_cancel: CancellationToken::new(),
+            qr_payload: String::new(),
+            qr_status: String::new(),
+            report_status: String::new(),
+            export_metadata_file: None,
+            export_format: ExportFormat::Erc721,
+            export_uri_base: String::new(),
+            export_external_url: String::new(),
+            export_rights: String::new(),
+            export_checksum_algorithm: checksums::ChecksumAlgorithm::Sha256,
+            export_bsd_style: false,
+            export_state: Arc::new(Mutex::new(ExportState::Idle)),
+            export_cancel: CancellationToken::new(),
+            compare_old_file: None,
+            compare_new_file: None,
+            compare_diff: None,
+            compare_status: String::new(),
+            catalogue_search: String::new(),
+            catalogue_field: CatalogueField::Any,
+            catalogue_results: Vec::new(),
+            catalogue_status: String::new(),
+            catalogue_scan_state: Arc::new(Mutex::new(CatalogueScanState::Idle)),
         }
     }
 }
@@ -116,6 +378,9 @@ impl App for GuiApp {
             ui.horizontal(|ui| {
                 ui.selectable_value(&mut self.selected_tab, Tab::Generate, "Generate");
                 ui.selectable_value(&mut self.selected_tab, Tab::Verify, "Verify");
+                ui.selectable_value(&mut self.selected_tab, Tab::Export, "Export");
+                ui.selectable_value(&mut self.selected_tab, Tab::Compare, "Compare");
+                ui.selectable_value(&mut self.selected_tab, Tab::Catalogue, "Catalogue");
             });
             
             ui.separator();
@@ -123,18 +388,9 @@ impl App for GuiApp {
             match self.selected_tab {
                 Tab::Generate => self.render_generate_tab(ui),
                 Tab::Verify => self.render_verify_tab(ui),
-            }
-            
-            // Request continuous updates when processing
-            if let Ok(state) = self.generation_state.lock() {
-                if matches!(&*state, GenerationState::Processing { .. }) {
-                    ctx.request_repaint();
-                }
-            }
-            if let Ok(state) = self.verification_state.lock() {
-                if matches!(&*state, VerificationState::Processing { .. }) {
-                    ctx.request_repaint();
-                }
+                Tab::Export => self.render_export_tab(ui),
+                Tab::Compare => self.render_compare_tab(ui),
+                Tab::Catalogue => self.render_catalogue_tab(ui),
             }
         });
     }
@@ -188,6 +444,11 @@ impl GuiApp {
             ui.text_edit_singleline(&mut self.keywords);
             ui.label("Medium (comma-separated)");
             ui.text_edit_singleline(&mut self.medium);
+            ui.checkbox(&mut self.compute_ipfs_cid, "Record IPFS CIDs (CIDv1, computed offline)");
+            ui.checkbox(&mut self.use_hash_cache, "Reuse hashes of unchanged files (hash cache)");
+            if self.use_hash_cache {
+                ui.checkbox(&mut self.rehash, "Re-hash every file this time");
+            }
             
             // Display certificate warning
             if !self.certificate_warning.is_empty() {
@@ -201,9 +462,21 @@ impl GuiApp {
             // Check if we can start generation
             let can_generate = self.folder.is_some() && !self.artwork_title.is_empty();
             
-            if ui.add_enabled(can_generate, egui::Button::new("Generate metadata")).clicked() {
-                self.start_generation();
-            }
+            ui.horizontal(|ui| {
+                if ui.add_enabled(can_generate, egui::Button::new("Generate metadata")).clicked() {
+                    self.start_generation(ui.ctx().clone());
+                }
+                // Refreshes the file hashes of an existing metadata file, keeping its descriptive fields
+                if ui.add_enabled(self.folder.is_some(), egui::Button::new("Update existing metadata")).clicked() {
+                    let mut dialog = FileDialog::new().add_filter("JSON", &["json"]);
+                    if let Some(folder) = &self.folder {
+                        dialog = dialog.set_directory(folder);
+                    }
+                    if let Some(metadata_path) = dialog.pick_file() {
+                        self.start_update(ui.ctx().clone(), metadata_path);
+                    }
+                }
+            });
 
             ui.separator();
             
@@ -214,7 +487,9 @@ impl GuiApp {
                         ui.label(&self.status);
                     }
                     GenerationState::Processing { current_file, file_progress, overall_progress, total_files, processed_files } => {
-                        ui.label(format!("Processing: {}/{} files", processed_files + 1, total_files));
+                        if *total_files > 0 {
+                            ui.label(format!("Processing: {}/{} files", processed_files + 1, total_files));
+                        }
                         ui.label(format!("Current file: {}", current_file));
                         ui.add_space(5.0);
                         
@@ -225,10 +500,72 @@ impl GuiApp {
                         ui.label("Overall progress:");
                         ui.add(egui::ProgressBar::new(*overall_progress).show_percentage());
                         ui.label(format!("Overall progress: {:.1}%", overall_progress * 100.0));
+
+                        ui.add_space(5.0);
+                        let cancel_button = egui::Button::new("Cancel");
+                        if ui.add_enabled(!self.generation_cancel.is_cancelled(), cancel_button).clicked() {
+                            self.generation_cancel.cancel();
+                        }
                     }
-                    GenerationState::Complete { output_path } => {
-                        ui.label(format!("✅ Saved to: {}", output_path.display()));
-                        ui.label("Metadata generation complete!");
+                    GenerationState::Complete { output_path, update } => {
+                        match update {
+                            Some(summary) if !summary.has_changes() => {
+                                ui.label(format!("✅ Already up to date: {}", output_path.display()));
+                            }
+                            Some(summary) => {
+                                ui.label(format!("✅ Updated: {}", output_path.display()));
+                                for file_name in &summary.added {
+                                    ui.label(format!("➕ Added: {}", file_name));
+                                }
+                                for file_name in &summary.changed {
+                                    ui.label(format!("✏️ Changed: {}", file_name));
+                                }
+                                for file_name in &summary.removed {
+                                    ui.label(format!("➖ Removed: {}", file_name));
+                                }
+                                if summary.certificate_changed {
+                                    ui.label("✏️ Certificate hash updated");
+                                }
+                                ui.label(format!("{} files unchanged", summary.unchanged));
+                            }
+                            None => {
+                                ui.label(format!("✅ Saved to: {}", output_path.display()));
+                                ui.label("Metadata generation complete!");
+                            }
+                        }
+
+                        ui.horizontal(|ui| {
+                            if ui.button("Save QR code").clicked() {
+                                if let Some(file) = FileDialog::new()
+                                    .add_filter("PNG image", &["png"])
+                                    .add_filter("SVG image", &["svg"])
+                                    .set_file_name("certificate_qr.png")
+                                    .save_file() {
+                                    self.qr_status = match certificate_qr::QrPayload::from_metadata_file(output_path)
+                                        .and_then(|payload| payload.write_to_file(&file)) {
+                                        Ok(()) => format!("✅ QR code saved to: {}", file.display()),
+                                        Err(e) => format!("❌ Error: {}", e),
+                                    };
+                                }
+                            }
+                            if ui.button("Save certificate").clicked() {
+                                if let Some(file) = FileDialog::new()
+                                    .add_filter("SVG image", &["svg"])
+                                    .set_file_name("certificate.svg")
+                                    .save_file() {
+                                    self.qr_status = match save_certificate(output_path, &file) {
+                                        Ok(()) => format!("✅ Certificate saved to: {}", file.display()),
+                                        Err(e) => format!("❌ Error: {}", e),
+                                    };
+                                }
+                            }
+                        });
+                        if !self.qr_status.is_empty() {
+                            ui.label(&self.qr_status);
+                        }
+                    }
+                    GenerationState::Cancelled => {
+                        ui.label("⏹️ Cancelled; no metadata file was written");
                     }
                     GenerationState::Error { message } => {
                         ui.label(format!("❌ Error: {}", message));
@@ -249,6 +586,8 @@ impl GuiApp {
             if ui.button("Browse for metadata file").clicked() {
                 if let Some(file) = FileDialog::new()
                     .add_filter("JSON files", &["json"])
+                    .add_filter("Checksum manifests", &["sha256", "sha512", "sha1", "md5", "b3", "txt"])
+                    .add_filter("All files", &["*"])
                     .pick_file() {
                     self.metadata_file = Some(file.clone());
                     
@@ -265,23 +604,113 @@ impl GuiApp {
             ui.add_space(10.0);
             
             // Base folder selection
-            ui.label("Select base folder (where the artwork files are located):");
-            if ui.button("Browse for base folder").clicked() {
-                if let Some(dir) = FileDialog::new().pick_folder() {
-                    self.base_folder = Some(dir);
+            ui.label("Select base folder (where the artwork files are located) or a ZIP/TAR archive of them:");
+            ui.horizontal(|ui| {
+                if ui.button("Browse for base folder").clicked() {
+                    if let Some(dir) = FileDialog::new().pick_folder() {
+                        self.base_folder = Some(dir);
+                    }
                 }
-            }
+                if ui.button("Browse for archive").clicked() {
+                    if let Some(file) = FileDialog::new()
+                        .add_filter("Archives", &["zip", "tar", "zst", "tzst"])
+                        .pick_file() {
+                        self.base_folder = Some(file);
+                    }
+                }
+            });
             if let Some(base_folder) = &self.base_folder {
-                ui.label(format!("Base folder: {}", base_folder.display()));
+                ui.label(format!("Base: {}", base_folder.display()));
             }
             
             ui.add_space(10.0);
             
+            ui.checkbox(&mut self.resume_verification, "Resume an interrupted verification (keeps a checkpoint next to the metadata file)");
+
             // Check if we can start verification
             let can_verify = self.metadata_file.is_some() && self.base_folder.is_some();
             
             if ui.add_enabled(can_verify, egui::Button::new("Verify files")).clicked() {
-                self.start_verification();
+                self.start_verification(ui.ctx());
+            }
+            if ui.button("Validate BagIt bag").clicked() {
+                if let Some(bag_path) = FileDialog::new().pick_folder() {
+                    self.start_bag_validation(ui.ctx(), bag_path);
+                }
+            }
+            if ui.button("Verify edition archive").clicked() {
+                if let Some(archive_path) = FileDialog::new()
+                    .add_filter("Edition archives", &["zip", "tar", "zst", "tzst"])
+                    .pick_file() {
+                    self.start_archive_verification(ui.ctx(), archive_path);
+                }
+            }
+
+            ui.add_space(10.0);
+
+            // Folder watch with a status indicator that turns red while any guarded file differs
+            let watch_state = self.watch_state.lock().map(|state| state.clone()).unwrap_or(WatchState::Idle);
+            match &watch_state {
+                WatchState::Active(status) => {
+                    if ui.add_enabled(!self.watch_cancel.is_cancelled(), egui::Button::new("Stop watching")).clicked() {
+                        self.watch_cancel.cancel();
+                    }
+                    if !status.watching {
+                        ui.label("⏳ Checking files before watching...");
+                    } else if status.changed_files.is_empty() {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(0, 255, 0),
+                            format!("● Watching {} files: all match their recorded hashes", status.total_files),
+                        );
+                    } else {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(255, 0, 0),
+                            format!("● {} of {} watched files changed: {}", status.changed_files.len(), status.total_files,
+                                status.changed_files.iter().cloned().collect::<Vec<_>>().join(", ")),
+                        );
+                    }
+                    egui::ScrollArea::vertical().id_source("watch_alerts").max_height(120.0).show(ui, |ui| {
+                        for alert in status.alerts.iter().rev() {
+                            ui.label(alert);
+                        }
+                    });
+                }
+                WatchState::Idle | WatchState::Error { .. } => {
+                    let can_watch = self.metadata_file.as_ref().is_some_and(|file| !checksums::is_checksum_manifest(file))
+                        && self.base_folder.as_ref().is_some_and(|folder| folder.is_dir());
+                    if ui.add_enabled(can_watch, egui::Button::new("Watch folder for changes")).clicked() {
+                        self.start_watch(ui.ctx());
+                    }
+                    if let WatchState::Error { message } = &watch_state {
+                        ui.label(format!("❌ Watch stopped: {}", message));
+                    }
+                }
+            }
+
+            ui.add_space(10.0);
+
+            // Certificate QR code check
+            ui.label("Certificate QR code (paste the scanned text):");
+            ui.text_edit_singleline(&mut self.qr_payload);
+            let can_check_qr = self.metadata_file.is_some() && !self.qr_payload.trim().is_empty();
+            if ui.add_enabled(can_check_qr, egui::Button::new("Check QR code")).clicked() {
+                if let Some(metadata_file) = &self.metadata_file {
+                    self.qr_status = match certificate_qr::verify_qr_payload(&self.qr_payload, metadata_file) {
+                        Ok(verification) => match verification.ensure_valid(metadata_file) {
+                            Ok(()) => "✅ Certificate QR code matches the metadata file!".to_string(),
+                            Err(e) => format!(
+                                "❌ {} (artwork ID: {}, edition: {})",
+                                e.message(),
+                                if verification.artwork_id_matches { "ok" } else { "mismatch" },
+                                if verification.edition_number_matches { "ok" } else { "mismatch" },
+                            ),
+                        },
+                        Err(e) => format!("❌ Error: {}", e),
+                    };
+                }
+            }
+            if !self.qr_status.is_empty() {
+                ui.label(&self.qr_status);
             }
             
             ui.separator();
@@ -293,7 +722,11 @@ impl GuiApp {
                         ui.label("Select a metadata file and base folder to start verification.");
                     }
                     VerificationState::Processing { current_file, file_progress, overall_progress, total_files, processed_files } => {
-                        ui.label(format!("Verifying: {}/{} files", processed_files + 1, total_files));
+                        if *total_files > 0 {
+                            ui.label(format!("Verifying: {}/{} files", processed_files + 1, total_files));
+                        } else {
+                            ui.label(format!("Verifying: {} archive entries read", processed_files));
+                        }
                         ui.label(format!("Current file: {}", current_file));
                         ui.add_space(5.0);
                         
@@ -304,6 +737,12 @@ impl GuiApp {
                         ui.label("Overall progress:");
                         ui.add(egui::ProgressBar::new(*overall_progress).show_percentage());
                         ui.label(format!("Overall progress: {:.1}%", overall_progress * 100.0));
+
+                        ui.add_space(5.0);
+                        let cancel_button = egui::Button::new("Cancel");
+                        if ui.add_enabled(!self.verification_cancel.is_cancelled(), cancel_button).clicked() {
+                            self.verification_cancel.cancel();
+                        }
                     }
                     VerificationState::Complete { report } => {
                         ui.label("✅ Verification complete!");
@@ -315,6 +754,19 @@ impl GuiApp {
                         ui.label(format!("Valid files: {}", report.valid_files));
                         ui.label(format!("Invalid files: {}", report.invalid_files));
                         
+                        // Merkle root verification
+                        match report.merkle_root_valid {
+                            Some(true) => {
+                                ui.colored_label(egui::Color32::from_rgb(0, 255, 0), "✅ Merkle root matches the files on disk!");
+                            }
+                            Some(false) => {
+                                ui.colored_label(egui::Color32::from_rgb(255, 0, 0), "❌ Merkle root does not match the files on disk!");
+                            }
+                            None => {
+                                ui.label("ℹ️ No Merkle root recorded");
+                            }
+                        }
+
                         // Certificate verification
                         if let Some(certificate_valid) = report.certificate_valid {
                             if certificate_valid {
@@ -334,6 +786,27 @@ impl GuiApp {
                         } else {
                             ui.colored_label(egui::Color32::from_rgb(255, 0, 0), "❌ Some files or certificate are invalid!");
                         }
+
+                        // The format follows the extension picked in the save dialog
+                        if ui.button("Export report").clicked() {
+                            let mut dialog = FileDialog::new().set_file_name("verification_report.html");
+                            for format in report::ReportFormat::ALL {
+                                dialog = dialog.add_filter(format.label(), &[format.extension()]);
+                            }
+                            if let Some(file) = dialog.save_file() {
+                                let format = report::ReportFormat::from_path(&file).unwrap_or(report::ReportFormat::Html);
+                                let subject = self.metadata_file.as_ref().or(self.base_folder.as_ref())
+                                    .map(|path| path.display().to_string())
+                                    .unwrap_or_default();
+                                self.report_status = match report::write_report(report, format, &subject, &file) {
+                                    Ok(()) => format!("✅ {} report saved to: {}", format.label(), file.display()),
+                                    Err(e) => format!("❌ Error: {}", e),
+                                };
+                            }
+                        }
+                        if !self.report_status.is_empty() {
+                            ui.label(&self.report_status);
+                        }
                         
                         ui.add_space(10.0);
                         
@@ -357,6 +830,9 @@ impl GuiApp {
                             }
                         });
                     }
+                    VerificationState::Cancelled => {
+                        ui.label("⏹️ Verification cancelled");
+                    }
                     VerificationState::Error { message } => {
                         ui.label(format!("❌ Error: {}", message));
                     }
@@ -365,7 +841,397 @@ impl GuiApp {
         });
     }
 
-    fn start_generation(&self) {
+    fn render_export_tab(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().max_height(f32::INFINITY).show(ui, |ui| {
+            ui.label("Export Metadata to Other Standards");
+            ui.add_space(10.0);
+
+            ui.label("Select metadata file:");
+            if ui.button("Browse for metadata file").clicked() {
+                if let Some(file) = FileDialog::new()
+                    .add_filter("JSON files", &["json"])
+                    .pick_file() {
+                    self.export_metadata_file = Some(file);
+                }
+            }
+            if let Some(metadata_file) = &self.export_metadata_file {
+                ui.label(format!("Metadata file: {}", metadata_file.display()));
+            }
+
+            ui.add_space(10.0);
+
+            egui::ComboBox::from_label("Format")
+                .selected_text(self.export_format.label())
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.export_format, ExportFormat::Erc721, ExportFormat::Erc721.label());
+                    ui.selectable_value(&mut self.export_format, ExportFormat::Tzip21, ExportFormat::Tzip21.label());
+                    ui.selectable_value(&mut self.export_format, ExportFormat::JsonLd, ExportFormat::JsonLd.label());
+                    ui.selectable_value(&mut self.export_format, ExportFormat::XmpSidecars, ExportFormat::XmpSidecars.label());
+                    ui.selectable_value(&mut self.export_format, ExportFormat::DublinCore, ExportFormat::DublinCore.label());
+                    ui.selectable_value(&mut self.export_format, ExportFormat::BagIt, ExportFormat::BagIt.label());
+                    ui.selectable_value(&mut self.export_format, ExportFormat::Checksums, ExportFormat::Checksums.label());
+                    ui.selectable_value(&mut self.export_format, ExportFormat::Archive, ExportFormat::Archive.label());
+                });
+
+            ui.label("Asset URI base (optional, e.g. ipfs://bafy.../)");
+            ui.text_edit_singleline(&mut self.export_uri_base);
+            match self.export_format {
+                ExportFormat::Erc721 => {
+                    ui.label("External URL (optional)");
+                    ui.text_edit_singleline(&mut self.export_external_url);
+                }
+                ExportFormat::JsonLd => {
+                    ui.label("Page URL (optional)");
+                    ui.text_edit_singleline(&mut self.export_external_url);
+                    ui.label("Save as .html to get a <script> tag for embedding in a page");
+                }
+                ExportFormat::Tzip21 | ExportFormat::XmpSidecars | ExportFormat::DublinCore => {
+                    ui.label("Rights (optional)");
+                    ui.text_edit_singleline(&mut self.export_rights);
+                }
+                ExportFormat::BagIt => {
+                    ui.label("Choose an empty folder for the bag; files are copied from the metadata file's folder");
+                }
+                ExportFormat::Checksums => {
+                    egui::ComboBox::from_label("Algorithm")
+                        .selected_text(self.export_checksum_algorithm.bsd_tag())
+                        .show_ui(ui, |ui| {
+                            for algorithm in checksums::ChecksumAlgorithm::ALL {
+                                ui.selectable_value(&mut self.export_checksum_algorithm, algorithm, algorithm.bsd_tag());
+                            }
+                        });
+                    ui.checkbox(&mut self.export_bsd_style, "BSD style (SHA256 (file) = hash)");
+                }
+                ExportFormat::Archive => {
+                    ui.label("Save as .zip or .tar.zst; files are read from the metadata file's folder");
+                }
+            }
+
+            ui.add_space(10.0);
+
+            let exporting = matches!(self.export_state.lock().as_deref(), Ok(ExportState::Processing { .. }));
+            let can_export = self.export_metadata_file.is_some() && !exporting;
+            if ui.add_enabled(can_export, egui::Button::new("Export")).clicked() {
+                // XMP sidecars and bags are written into a folder, every other format to a single file
+                let output = if matches!(self.export_format, ExportFormat::XmpSidecars | ExportFormat::BagIt) {
+                    FileDialog::new().pick_folder()
+                } else {
+                    let file_name = if self.export_format == ExportFormat::Checksums {
+                        self.export_checksum_algorithm.default_file_name()
+                    } else {
+                        self.export_format.default_file_name()
+                    };
+                    FileDialog::new()
+                        .set_file_name(file_name)
+                        .save_file()
+                };
+                if let Some(output) = output {
+                    self.start_export(ui.ctx(), output);
+                }
+            }
+
+            ui.separator();
+
+            // Packaging, bags and checksum manifests read every artwork file, so they report progress
+            if let Ok(state) = self.export_state.lock() {
+                match &*state {
+                    ExportState::Idle => {}
+                    ExportState::Processing { current_file, file_progress, overall_progress, total_files, processed_files } => {
+                        if *total_files > 0 {
+                            ui.label(format!("Exporting: {}/{} files", processed_files + 1, total_files));
+                        }
+                        ui.label(format!("Current file: {}", current_file));
+                        ui.add_space(5.0);
+
+                        ui.label("File progress:");
+                        ui.add(egui::ProgressBar::new(*file_progress).show_percentage());
+
+                        ui.add_space(5.0);
+                        ui.label("Overall progress:");
+                        ui.add(egui::ProgressBar::new(*overall_progress).show_percentage());
+
+                        ui.add_space(5.0);
+                        let cancel_button = egui::Button::new("Cancel");
+                        if ui.add_enabled(!self.export_cancel.is_cancelled(), cancel_button).clicked() {
+                            self.export_cancel.cancel();
+                        }
+                    }
+                    ExportState::Complete { output_path } => {
+                        ui.label(format!("✅ Exported to: {}", output_path.display()));
+                    }
+                    ExportState::Cancelled => {
+                        ui.label("⏹️ Export cancelled; the partial output was removed");
+                    }
+                    ExportState::Error { message } => {
+                        ui.label(format!("❌ Error: {}", message));
+                    }
+                }
+            }
+        });
+    }
+
+    fn render_catalogue_tab(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().max_height(f32::INFINITY).show(ui, |ui| {
+            ui.label("Editions generated or verified on this machine");
+            ui.add_space(10.0);
+
+            let mut search = false;
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("catalogue_field")
+                    .selected_text(self.catalogue_field.label())
+                    .show_ui(ui, |ui| {
+                        for field in CatalogueField::ALL {
+                            ui.selectable_value(&mut self.catalogue_field, field, field.label());
+                        }
+                    });
+                let response = ui.text_edit_singleline(&mut self.catalogue_search);
+                search = response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                search |= ui.button("Search").clicked();
+            });
+            let scan_state = self.catalogue_scan_state.lock().map(|state| state.clone()).unwrap_or(CatalogueScanState::Idle);
+            if let CatalogueScanState::Complete { status } = &scan_state {
+                self.catalogue_status = status.clone();
+                if let Ok(mut state) = self.catalogue_scan_state.lock() {
+                    *state = CatalogueScanState::Idle;
+                }
+                search = true;
+            }
+            ui.horizontal(|ui| {
+                let scanning = matches!(scan_state, CatalogueScanState::Scanning { .. });
+                if ui.add_enabled(!scanning, egui::Button::new("Scan folder…")).clicked() {
+                    if let Some(folder) = FileDialog::new().pick_folder() {
+                        self.start_catalogue_scan(ui.ctx(), folder);
+                    }
+                }
+                if let CatalogueScanState::Scanning { folder } = &scan_state {
+                    ui.spinner();
+                    ui.label(format!("Scanning {}…", folder.display()));
+                }
+                if let Some(path) = catalogue::Catalogue::default_path() {
+                    ui.weak(path.display().to_string());
+                }
+            });
+
+            if search {
+                let query = self.catalogue_field.query(&self.catalogue_search);
+                match catalogue::Catalogue::open_default().and_then(|catalogue| catalogue.search(&query)) {
+                    Ok(results) => self.catalogue_results = results,
+                    Err(e) => {
+                        self.catalogue_results.clear();
+                        self.catalogue_status = format!("❌ Error: {}", e);
+                    }
+                }
+            }
+
+            if !self.catalogue_status.is_empty() {
+                ui.label(&self.catalogue_status);
+            }
+            ui.separator();
+
+            ui.label(format!("{} editions", self.catalogue_results.len()));
+            let mut verify = None;
+            egui::Grid::new("catalogue_results").striped(true).show(ui, |ui| {
+                ui.strong("Creator");
+                ui.strong("Title");
+                ui.strong("Edition");
+                ui.strong("Gallery");
+                ui.strong("Last verified");
+                ui.strong("");
+                ui.end_row();
+                for entry in &self.catalogue_results {
+                    ui.label(&entry.artwork_creator);
+                    ui.label(&entry.artwork_title).on_hover_text(entry.metadata_path.display().to_string());
+                    ui.label(format!("{}/{}", entry.edition_number, entry.total_editions));
+                    ui.label(&entry.gallery);
+                    match (entry.last_verification_valid, &entry.last_verified_at) {
+                        (Some(true), Some(verified_at)) => {
+                            ui.colored_label(egui::Color32::from_rgb(0, 255, 0), format!("✅ {}", verified_at));
+                        }
+                        (Some(false), Some(verified_at)) => {
+                            ui.colored_label(egui::Color32::from_rgb(255, 0, 0), format!("❌ {}", verified_at));
+                        }
+                        _ => {
+                            ui.label("never");
+                        }
+                    }
+                    if ui.button("Verify").clicked() {
+                        verify = Some(entry.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+
+            if let Some(entry) = verify {
+                self.metadata_file = Some(entry.metadata_path);
+                self.base_folder = Some(entry.base_folder);
+                self.selected_tab = Tab::Verify;
+            }
+        });
+    }
+
+    fn render_compare_tab(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().max_height(f32::INFINITY).show(ui, |ui| {
+            ui.label("Compare two metadata files, e.g. two drafts or two editions");
+            ui.add_space(10.0);
+
+            ui.horizontal(|ui| {
+                if ui.button("Browse for old metadata file").clicked() {
+                    if let Some(file) = FileDialog::new().add_filter("JSON files", &["json"]).pick_file() {
+                        self.compare_old_file = Some(file);
+                        self.compare_diff = None;
+                    }
+                }
+                if let Some(file) = &self.compare_old_file {
+                    ui.label(format!("Old: {}", file.display()));
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Browse for new metadata file").clicked() {
+                    if let Some(file) = FileDialog::new().add_filter("JSON files", &["json"]).pick_file() {
+                        self.compare_new_file = Some(file);
+                        self.compare_diff = None;
+                    }
+                }
+                if let Some(file) = &self.compare_new_file {
+                    ui.label(format!("New: {}", file.display()));
+                }
+            });
+
+            ui.add_space(10.0);
+            let can_compare = self.compare_old_file.is_some() && self.compare_new_file.is_some();
+            if ui.add_enabled(can_compare, egui::Button::new("Compare")).clicked() {
+                if let (Some(old_file), Some(new_file)) = (&self.compare_old_file, &self.compare_new_file) {
+                    match diff::diff_metadata_files(old_file, new_file) {
+                        Ok(diff) => {
+                            self.compare_diff = Some(diff);
+                            self.compare_status.clear();
+                        }
+                        Err(e) => {
+                            self.compare_diff = None;
+                            self.compare_status = format!("❌ Error: {}", e);
+                        }
+                    }
+                }
+            }
+
+            ui.separator();
+            if let Some(diff) = &self.compare_diff {
+                if diff.is_empty() {
+                    ui.colored_label(egui::Color32::from_rgb(0, 255, 0), "✅ No differences");
+                }
+
+                if !diff.field_changes.is_empty() {
+                    ui.label("Fields:");
+                    egui::Grid::new("compare_fields").striped(true).show(ui, |ui| {
+                        ui.strong("Field");
+                        ui.strong("Old");
+                        ui.strong("New");
+                        ui.end_row();
+                        for change in &diff.field_changes {
+                            ui.label(&change.field);
+                            ui.label(diff::display_value(&change.old_value));
+                            ui.label(diff::display_value(&change.new_value));
+                            ui.end_row();
+                        }
+                    });
+                    ui.add_space(10.0);
+                }
+
+                if !diff.file_changes.is_empty() {
+                    ui.label("Files:");
+                    for change in &diff.file_changes {
+                        let (color, marker) = match change.kind {
+                            diff::FileChangeKind::Added => (egui::Color32::from_rgb(0, 255, 0), "➕"),
+                            diff::FileChangeKind::Removed => (egui::Color32::from_rgb(255, 0, 0), "➖"),
+                            diff::FileChangeKind::Rehashed | diff::FileChangeKind::Modified => {
+                                (egui::Color32::from_rgb(255, 165, 0), "✏️")
+                            }
+                        };
+                        ui.colored_label(color, format!("{} {} ({})", marker, change.path, change.description()));
+                    }
+                    ui.add_space(10.0);
+                }
+
+                ui.label(format!(
+                    "{} field(s) changed, {} file(s) changed, {} file(s) unchanged",
+                    diff.field_changes.len(),
+                    diff.file_changes.len(),
+                    diff.unchanged_files
+                ));
+
+                ui.horizontal(|ui| {
+                    if ui.button("Save as text").clicked() {
+                        if let Some(file) = FileDialog::new().set_file_name("metadata_diff.txt").save_file() {
+                            self.compare_status = match std::fs::write(&file, diff.to_text()) {
+                                Ok(()) => format!("✅ Saved to: {}", file.display()),
+                                Err(e) => format!("❌ Error: {}", e),
+                            };
+                        }
+                    }
+                    if ui.button("Save as JSON").clicked() {
+                        if let Some(file) = FileDialog::new()
+                            .add_filter("JSON files", &["json"])
+                            .set_file_name("metadata_diff.json")
+                            .save_file() {
+                            self.compare_status = match serde_json::to_string_pretty(diff)
+                                .map_err(metadata_generator::Error::from)
+                                .and_then(|json| std::fs::write(&file, json).with_path(&file)) {
+                                Ok(()) => format!("✅ Saved to: {}", file.display()),
+                                Err(e) => format!("❌ Error: {}", e),
+                            };
+                        }
+                    }
+                });
+            }
+            if !self.compare_status.is_empty() {
+                ui.label(&self.compare_status);
+            }
+        });
+    }
+
+    fn start_export(&mut self, ctx: &egui::Context, output: PathBuf) {
+        let Some(metadata_path) = self.export_metadata_file.clone() else {
+            return;
+        };
+        let optional = |value: &String| if value.trim().is_empty() { None } else { Some(value.trim().to_string()) };
+        let job = ExportJob {
+            format: self.export_format,
+            metadata_path,
+            uri_base: optional(&self.export_uri_base),
+            external_url: optional(&self.export_external_url),
+            rights: optional(&self.export_rights),
+            checksum_algorithm: self.export_checksum_algorithm,
+            bsd_style: self.export_bsd_style,
+        };
+        let export_state = Arc::clone(&self.export_state);
+        if let Ok(mut state) = export_state.lock() {
+            *state = ExportState::Processing {
+                current_file: String::new(),
+                file_progress: 0.0,
+                overall_progress: 0.0,
+                total_files: 0,
+                processed_files: 0,
+            };
+        }
+        self.export_cancel = CancellationToken::new();
+        let cancellation = self.export_cancel.clone();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let generator = export_generator(&export_state, &ctx, &cancellation);
+            let result = job.run(&generator, &output);
+            if let Ok(mut state) = export_state.lock() {
+                *state = match result {
+                    Ok(()) => ExportState::Complete { output_path: output },
+                    Err(e) if e.is_cancelled() => ExportState::Cancelled,
+                    Err(e) => ExportState::Error { message: e.to_string() },
+                };
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn start_generation(&mut self, ctx: egui::Context) {
         let folder = self.folder.clone();
         let artwork_id = self.artwork_id.clone();
         let artwork_title = self.artwork_title.clone();
@@ -380,11 +1246,14 @@ impl GuiApp {
         let gallery = self.gallery.clone();
         let keywords = self.keywords.clone();
         let medium = self.medium.clone();
+        let compute_ipfs_cid = self.compute_ipfs_cid;
+        let use_hash_cache = self.use_hash_cache;
+        let rehash = self.rehash;
         let generation_state = Arc::clone(&self.generation_state);
+        self.generation_cancel = CancellationToken::new();
+        let cancellation = self.generation_cancel.clone();
 
         thread::spawn(move || {
-            let generation_state_clone = Arc::clone(&generation_state);
-            
             // Parse keywords and medium from comma-separated strings
             let keywords_vec: Vec<String> = keywords.split(',')
                 .map(|s| s.trim().to_string())
@@ -426,26 +1295,26 @@ impl GuiApp {
                 certificate_of_authenticity,
                 certificate_hash: None,
                 artwork_files: Vec::new(),
+                merkle_root: None,
+                ipfs_root_cid: None,
             };
 
-            // Create generator with GUI progress callback
-            let generator = MetadataGenerator::new_gui()
-                .with_progress_callback(ProgressCallback::Gui(Box::new(move |current_file, file_progress, overall_progress| {
-                    if let Ok(mut state) = generation_state_clone.lock() {
-                        *state = GenerationState::Processing {
-                            current_file,
-                            file_progress,
-                            overall_progress,
-                            total_files: 0, // Will be updated
-                            processed_files: (overall_progress * 100.0) as usize,
-                        };
-                    }
-                })));
+            let folder = folder.unwrap();
+            let generator =
+                generation_generator(&generation_state, &ctx, &cancellation, &folder, compute_ipfs_cid, use_hash_cache, rehash);
 
-            match generator.generate_metadata_async(&folder.unwrap(), &metadata) {
+            match generator.generate_metadata(&folder, &metadata) {
                 Ok(output_path) => {
+                    if let Err(e) = catalogue::record_in_default_catalogue(&output_path, &folder, None) {
+                        eprintln!("Warning: Could not update the catalogue: {}", e);
+                    }
+                    if let Ok(mut state) = generation_state.lock() {
+                        *state = GenerationState::Complete { output_path, update: None };
+                    }
+                }
+                Err(e) if e.is_cancelled() => {
                     if let Ok(mut state) = generation_state.lock() {
-                        *state = GenerationState::Complete { output_path };
+                        *state = GenerationState::Cancelled;
                     }
                 }
                 Err(e) => {
@@ -456,47 +1325,326 @@ impl GuiApp {
                     }
                 }
             }
+            ctx.request_repaint();
         });
     }
 
-    fn start_verification(&self) {
-        let metadata_file = self.metadata_file.clone();
-        let base_folder = self.base_folder.clone();
-        let verification_state = Arc::clone(&self.verification_state);
+    fn start_update(&mut self, ctx: egui::Context, metadata_path: PathBuf) {
+        let Some(folder) = self.folder.clone() else {
+            return;
+        };
+        let compute_ipfs_cid = self.compute_ipfs_cid;
+        let use_hash_cache = self.use_hash_cache;
+        let rehash = self.rehash;
+        let generation_state = Arc::clone(&self.generation_state);
+        self.generation_cancel = CancellationToken::new();
+        let cancellation = self.generation_cancel.clone();
 
         thread::spawn(move || {
-            let verification_state_clone = Arc::clone(&verification_state);
-            
-            // Create generator with GUI progress callback
+            let generator =
+                generation_generator(&generation_state, &ctx, &cancellation, &folder, compute_ipfs_cid, use_hash_cache, rehash);
+
+            let result = generator.update_metadata(&folder, &metadata_path);
+            if let Ok(summary) = &result {
+                if let Err(e) = catalogue::record_in_default_catalogue(&summary.metadata_path, &folder, None) {
+                    eprintln!("Warning: Could not update the catalogue: {}", e);
+                }
+            }
+            if let Ok(mut state) = generation_state.lock() {
+                *state = match result {
+                    Ok(summary) => GenerationState::Complete {
+                        output_path: summary.metadata_path.clone(),
+                        update: Some(summary),
+                    },
+                    Err(e) if e.is_cancelled() => GenerationState::Cancelled,
+                    Err(e) => GenerationState::Error { message: e.to_string() },
+                };
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn start_catalogue_scan(&mut self, ctx: &egui::Context, folder: PathBuf) {
+        let scan_state = Arc::clone(&self.catalogue_scan_state);
+        if let Ok(mut state) = scan_state.lock() {
+            *state = CatalogueScanState::Scanning { folder: folder.clone() };
+        }
+        let ctx = ctx.clone();
+
+        // Opens its own connection, so the UI thread never waits on the database
+        thread::spawn(move || {
+            let status = match catalogue::Catalogue::open_default().and_then(|catalogue| catalogue.scan(&folder)) {
+                Ok(summary) if summary.skipped.is_empty() => {
+                    format!("✅ Recorded {} metadata files", summary.recorded)
+                }
+                Ok(summary) => format!(
+                    "✅ Recorded {} metadata files, skipped {} unreadable ones",
+                    summary.recorded,
+                    summary.skipped.len()
+                ),
+                Err(e) => format!("❌ Error: {}", e),
+            };
+            if let Ok(mut state) = scan_state.lock() {
+                *state = CatalogueScanState::Complete { status };
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn start_watch(&mut self, ctx: &egui::Context) {
+        let (Some(metadata_file), Some(base_folder)) = (self.metadata_file.clone(), self.base_folder.clone()) else {
+            return;
+        };
+        let watch_state = Arc::clone(&self.watch_state);
+        if let Ok(mut state) = watch_state.lock() {
+            *state = WatchState::Active(WatchStatus::default());
+        }
+        self.watch_cancel = CancellationToken::new();
+        let cancellation = self.watch_cancel.clone();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let observer_state = Arc::clone(&watch_state);
+            let observer_ctx = ctx.clone();
             let generator = MetadataGenerator::new_gui()
-                .with_progress_callback(ProgressCallback::Gui(Box::new(move |current_file, file_progress, overall_progress| {
-                    if let Ok(mut state) = verification_state_clone.lock() {
-                        *state = VerificationState::Processing {
-                            current_file,
-                            file_progress,
-                            overall_progress,
-                            total_files: 0, // Will be updated
-                            processed_files: (overall_progress * 100.0) as usize,
-                        };
+                .with_observer(move |event: &ProgressEvent| {
+                    let Ok(mut state) = observer_state.lock() else {
+                        return;
+                    };
+                    let WatchState::Active(status) = &mut *state else {
+                        return;
+                    };
+                    match event {
+                        // Files that already failed the first check count as changed
+                        ProgressEvent::FileCompleted { file_name, outcome: events::FileOutcome::Invalid, .. }
+                        | ProgressEvent::FileFailed { file_name, .. } => {
+                            status.changed_files.insert(file_name.clone());
+                        }
+                        ProgressEvent::WatchStarted { total_files, .. } => {
+                            status.watching = true;
+                            status.total_files = *total_files;
+                        }
+                        ProgressEvent::FileChanged { file_name, change, .. } => {
+                            if *change == events::FileChange::Restored {
+                                status.changed_files.remove(file_name);
+                            } else {
+                                status.changed_files.insert(file_name.clone());
+                            }
+                            status.alerts.push(event.to_string());
+                        }
+                        _ => return,
                     }
-                })));
+                    observer_ctx.request_repaint();
+                })
+                .with_cancellation(cancellation)
+                .with_read_retries(constants::DEFAULT_READ_RETRIES);
 
-            match generator.verify_metadata_file_with_progress(&metadata_file.unwrap(), &base_folder.unwrap()) {
-                Ok(report) => {
-                    if let Ok(mut state) = verification_state.lock() {
-                        *state = VerificationState::Complete { report };
-                    }
-                }
-                Err(e) => {
-                    if let Ok(mut state) = verification_state.lock() {
-                        *state = VerificationState::Error {
-                            message: e.to_string(),
-                        };
+            let result = generator.watch_metadata_file(&metadata_file, &base_folder);
+            if let Ok(mut state) = watch_state.lock() {
+                *state = match result {
+                    Ok(()) => WatchState::Idle,
+                    Err(e) => WatchState::Error { message: e.to_string() },
+                };
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn start_verification(&mut self, ctx: &egui::Context) {
+        let metadata_file = self.metadata_file.clone();
+        let base_folder = self.base_folder.clone();
+        let resume_verification = self.resume_verification;
+        let verification_state = Arc::clone(&self.verification_state);
+        let cancellation = self.restart_verification_cancel();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let generator = verification_generator(&verification_state, &ctx, &cancellation).with_checkpoint(resume_verification);
+            let metadata_file = metadata_file.unwrap();
+            let base_folder = base_folder.unwrap();
+            // A plain checksum list (SHA256SUMS, .md5, ...) can stand in for our metadata JSON
+            let result = if checksums::is_checksum_manifest(&metadata_file) {
+                generator.verify_checksum_manifest(&metadata_file, &base_folder)
+            } else {
+                let result = generator.verify_metadata_file_with_progress(&metadata_file, &base_folder);
+                if let Ok(report) = &result {
+                    if let Err(e) = catalogue::record_in_default_catalogue(&metadata_file, &base_folder, Some(report)) {
+                        eprintln!("Warning: Could not update the catalogue: {}", e);
                     }
                 }
-            }
+                result
+            };
+            finish_verification(&verification_state, &ctx, result);
         });
     }
+
+    fn start_archive_verification(&mut self, ctx: &egui::Context, archive_path: PathBuf) {
+        let verification_state = Arc::clone(&self.verification_state);
+        let cancellation = self.restart_verification_cancel();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let generator = verification_generator(&verification_state, &ctx, &cancellation);
+            let result = generator.verify_archive(&archive_path);
+            finish_verification(&verification_state, &ctx, result);
+        });
+    }
+
+    /// Each verification gets a fresh token so an earlier Cancel click does not stop it
+    fn restart_verification_cancel(&mut self) -> CancellationToken {
+        self.verification_cancel = CancellationToken::new();
+        self.verification_cancel.clone()
+    }
+
+    fn start_bag_validation(&mut self, ctx: &egui::Context, bag_path: PathBuf) {
+        let verification_state = Arc::clone(&self.verification_state);
+        let cancellation = self.restart_verification_cancel();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let generator = verification_generator(&verification_state, &ctx, &cancellation);
+            let result = bagit::validate_bag(&generator, &bag_path);
+            finish_verification(&verification_state, &ctx, result);
+        });
+    }
+}
+
+/// Current file, total files and zero-based position shown while a pass is running
+/// Certificate and completion events leave the progress display as it was
+fn processing_position(event: &ProgressEvent) -> Option<(String, usize, usize)> {
+    match event {
+        ProgressEvent::ScanStarted { total_files } => Some((String::new(), *total_files, 0)),
+        ProgressEvent::ResumedFromCheckpoint { completed_files, total_files } => {
+            Some((String::new(), *total_files, *completed_files))
+        }
+        ProgressEvent::FileStarted { index, total_files, .. }
+        | ProgressEvent::BytesHashed { index, total_files, .. }
+        | ProgressEvent::FileCompleted { index, total_files, .. }
+        | ProgressEvent::FileFailed { index, total_files, .. }
+        | ProgressEvent::FileRetrying { index, total_files, .. } => {
+            Some((event.file_name().unwrap_or_default().to_string(), *total_files, *index))
+        }
+        ProgressEvent::CertificateChecked { .. }
+        | ProgressEvent::Finished { .. }
+        | ProgressEvent::WatchStarted { .. }
+        | ProgressEvent::FileChanged { .. } => None,
+    }
+}
+
+/// Generator whose progress events update the Generate tab and wake the UI as they arrive
+fn generation_generator(
+    generation_state: &Arc<Mutex<GenerationState>>,
+    ctx: &egui::Context,
+    cancellation: &CancellationToken,
+    folder: &std::path::Path,
+    compute_ipfs_cid: bool,
+    use_hash_cache: bool,
+    rehash: bool,
+) -> MetadataGenerator {
+    let generation_state = Arc::clone(generation_state);
+    let ctx = ctx.clone();
+    let generator = MetadataGenerator::new_gui()
+        .with_observer(move |event: &ProgressEvent| {
+            let Some((current_file, total_files, processed_files)) = processing_position(event) else {
+                return;
+            };
+            if let Ok(mut state) = generation_state.lock() {
+                *state = GenerationState::Processing {
+                    current_file,
+                    file_progress: event.file_progress(),
+                    overall_progress: event.overall_progress(),
+                    total_files,
+                    processed_files,
+                };
+            }
+            ctx.request_repaint();
+        })
+        .with_cancellation(cancellation.clone())
+        .with_ipfs_cid(compute_ipfs_cid);
+
+    // The GUI keeps its hash cache next to the artwork files
+    if !use_hash_cache {
+        return generator;
+    }
+    let cache_path = hash_cache::HashCache::folder_cache_path(folder);
+    generator.with_hash_cache(hash_cache::HashCache::load(&cache_path).with_rehash(rehash))
+}
+
+/// Generator whose progress events update the Verify tab
+fn verification_generator(
+    verification_state: &Arc<Mutex<VerificationState>>,
+    ctx: &egui::Context,
+    cancellation: &CancellationToken,
+) -> MetadataGenerator {
+    let verification_state = Arc::clone(verification_state);
+    let ctx = ctx.clone();
+    MetadataGenerator::new_gui()
+        .with_observer(move |event: &ProgressEvent| {
+            let Some((current_file, total_files, processed_files)) = processing_position(event) else {
+                return;
+            };
+            if let Ok(mut state) = verification_state.lock() {
+                *state = VerificationState::Processing {
+                    current_file,
+                    file_progress: event.file_progress(),
+                    overall_progress: event.overall_progress(),
+                    total_files,
+                    processed_files,
+                };
+            }
+            ctx.request_repaint();
+        })
+        .with_cancellation(cancellation.clone())
+        .with_read_retries(constants::DEFAULT_READ_RETRIES)
+}
+
+/// Generator whose progress events update the Export tab
+fn export_generator(
+    export_state: &Arc<Mutex<ExportState>>,
+    ctx: &egui::Context,
+    cancellation: &CancellationToken,
+) -> MetadataGenerator {
+    let export_state = Arc::clone(export_state);
+    let ctx = ctx.clone();
+    MetadataGenerator::new_gui()
+        .with_observer(move |event: &ProgressEvent| {
+            let Some((current_file, total_files, processed_files)) = processing_position(event) else {
+                return;
+            };
+            if let Ok(mut state) = export_state.lock() {
+                *state = ExportState::Processing {
+                    current_file,
+                    file_progress: event.file_progress(),
+                    overall_progress: event.overall_progress(),
+                    total_files,
+                    processed_files,
+                };
+            }
+            ctx.request_repaint();
+        })
+        .with_cancellation(cancellation.clone())
+        .with_read_retries(constants::DEFAULT_READ_RETRIES)
+}
+
+fn finish_verification(
+    verification_state: &Arc<Mutex<VerificationState>>,
+    ctx: &egui::Context,
+    result: metadata_generator::Result<VerificationReport>,
+) {
+    if let Ok(mut state) = verification_state.lock() {
+        *state = match result {
+            Ok(report) => VerificationState::Complete { report },
+            Err(e) if e.is_cancelled() => VerificationState::Cancelled,
+            Err(e) => VerificationState::Error { message: e.to_string() },
+        };
+    }
+    ctx.request_repaint();
+}
+
+/// Writes a printable SVG certificate for the given metadata file
+fn save_certificate(metadata_path: &std::path::Path, output_path: &std::path::Path) -> metadata_generator::Result<()> {
+    let metadata = metadata_generator::read_metadata_file(metadata_path)?;
+    std::fs::write(output_path, certificate_qr::generate_certificate_svg(&metadata)?).with_path(output_path)
 }
 
 fn main() -> Result<(), eframe::Error> {
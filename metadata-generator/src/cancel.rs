@@ -0,0 +1,53 @@
+use std::{
+    fmt, io,
+    sync::{atomic::{AtomicBool, Ordering}, Arc},
+};
+
+/// Stop flag shared between whoever starts a pass and the thread doing the hashing
+/// Clones observe the same flag, so the GUI or a Ctrl-C handler can keep one and hand the other on
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Returns the cancelled error once `cancel` has been called, so long loops can bail out with `?`
+    pub fn check(&self) -> io::Result<()> {
+        if self.is_cancelled() {
+            Err(cancelled_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Payload of the error returned by a cancelled pass; not `ErrorKind::Interrupted`, which std retries
+#[derive(Debug)]
+struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+pub fn cancelled_error() -> io::Error {
+    io::Error::other(Cancelled)
+}
+
+/// Tells a cancelled pass apart from one that failed
+pub fn is_cancelled(error: &io::Error) -> bool {
+    error.get_ref().is_some_and(|inner| inner.is::<Cancelled>())
+}
@@ -0,0 +1,194 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fs::{self, File},
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+use serde::{Deserialize, Serialize};
+use crate::constants::should_ignore_file;
+use crate::cancel::CancellationToken;
+use crate::error::{Error, IoResultExt, Result};
+use crate::xmp::is_generated_sidecar;
+use crate::ipfs::{self, write_varint, BlockSink, Cid, DagLink, UnixFsFileBuilder, CHUNK_SIZE};
+use crate::metadata_generator::{read_metadata_file, write_metadata};
+
+/// Summary of a CAR export
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CarExport {
+    pub root_cid: String,
+    pub file_count: usize,
+    pub block_count: usize,
+    pub car_size: u64,
+}
+
+/// Encodes the CARv1 header `{"roots": [root], "version": 1}` as DAG-CBOR, prefixed with its length
+/// CIDv1 SHA2-256 CIDs are always 36 bytes, so the header has a fixed size
+fn encode_header(root: &Cid) -> Vec<u8> {
+    let cid_bytes = root.to_bytes();
+    let mut cbor = vec![0xa2]; // map with 2 entries, keys in DAG-CBOR canonical order
+    cbor.push(0x65);
+    cbor.extend_from_slice(b"roots");
+    cbor.push(0x81); // array with 1 element
+    cbor.extend_from_slice(&[0xd8, 0x2a]); // tag 42 (CID)
+    cbor.push(0x58); // byte string with 1-byte length
+    cbor.push(cid_bytes.len() as u8 + 1);
+    cbor.push(0x00); // multibase identity prefix
+    cbor.extend_from_slice(&cid_bytes);
+    cbor.push(0x67);
+    cbor.extend_from_slice(b"version");
+    cbor.push(0x01);
+
+    let mut header = Vec::new();
+    write_varint(&mut header, cbor.len() as u64);
+    header.extend_from_slice(&cbor);
+    header
+}
+
+/// Writes blocks to a CAR file, skipping blocks that were already written
+struct CarWriter {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    written: HashSet<Cid>,
+    cancellation: CancellationToken,
+}
+
+impl CarWriter {
+    fn write_block(&mut self, cid: &Cid, block: &[u8]) -> Result<()> {
+        if !self.written.insert(cid.clone()) {
+            return Ok(());
+        }
+        let cid_bytes = cid.to_bytes();
+        let mut length = Vec::new();
+        write_varint(&mut length, (cid_bytes.len() + block.len()) as u64);
+        length.extend_from_slice(&cid_bytes);
+        self.writer.write_all(&length).with_path(&self.path)?;
+        self.writer.write_all(block).with_path(&self.path)
+    }
+}
+
+/// Adds a single file to the DAG and returns a named link to it
+fn add_file(path: &Path, name: &str, car: &RefCell<CarWriter>) -> Result<DagLink> {
+    let block_sink: BlockSink = Box::new(|cid, block| car.borrow_mut().write_block(cid, block));
+    let mut builder = UnixFsFileBuilder::new().with_block_sink(block_sink);
+
+    let mut file = File::open(path).with_path(path)?;
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    loop {
+        car.borrow().cancellation.check().with_path(path)?;
+        let read = file.read(&mut buffer).with_path(path)?;
+        if read == 0 {
+            break;
+        }
+        builder.update(&buffer[..read])?;
+    }
+
+    let mut link = builder.finalize()?;
+    link.name = name.to_string();
+    Ok(link)
+}
+
+/// Adds every non-ignored file in a folder (non-recursive) and returns the links
+fn add_folder_files(folder_path: &Path, car: &RefCell<CarWriter>) -> Result<Vec<DagLink>> {
+    let mut links = Vec::new();
+    for entry in fs::read_dir(folder_path).with_path(folder_path)? {
+        let path = entry.with_path(folder_path)?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+        if should_ignore_file(&file_name) || is_generated_sidecar(&path) {
+            continue;
+        }
+        links.push(add_file(&path, &file_name, car)?);
+    }
+    Ok(links)
+}
+
+/// Builds a UnixFS DAG of the artwork folder, its certificate folder and the metadata JSON,
+/// and writes it as a CARv1 file whose single root is the folder's directory node
+/// A failed or cancelled export removes the partial CAR file
+pub fn export_car(
+    folder_path: &Path,
+    metadata_path: &Path,
+    output_path: &Path,
+    cancellation: &CancellationToken,
+) -> Result<CarExport> {
+    let result = write_car(folder_path, metadata_path, output_path, cancellation);
+    if result.is_err() {
+        let _ = fs::remove_file(output_path);
+    }
+    result
+}
+
+fn write_car(folder_path: &Path, metadata_path: &Path, output_path: &Path, cancellation: &CancellationToken) -> Result<CarExport> {
+    let mut file = File::create(output_path).with_path(output_path)?;
+
+    // The root is only known once every block is written, so reserve space for the header
+    let placeholder = encode_header(&Cid::placeholder());
+    file.write_all(&placeholder).with_path(output_path)?;
+
+    let car = RefCell::new(CarWriter {
+        path: output_path.to_path_buf(),
+        writer: BufWriter::new(file),
+        written: HashSet::new(),
+        cancellation: cancellation.clone(),
+    });
+
+    let mut links = add_folder_files(folder_path, &car)?;
+
+    // The folder scan skips metadata JSON named `*metadata.json`; any other name may already be in it
+    let metadata_name = metadata_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let already_scanned = links.iter().any(|link| link.name == metadata_name)
+        && fs::canonicalize(folder_path.join(&metadata_name)).ok() == fs::canonicalize(metadata_path).ok();
+    if !already_scanned {
+        links.push(add_file(metadata_path, &metadata_name, &car)?);
+    }
+
+    let certificate_folder = folder_path.join("certificate");
+    if certificate_folder.is_dir() {
+        let certificate_links = add_folder_files(&certificate_folder, &car)?;
+        if !certificate_links.is_empty() {
+            let mut certificate_link = ipfs::build_directory_node(certificate_links, &mut |cid, block| {
+                car.borrow_mut().write_block(cid, block)
+            })?;
+            certificate_link.name = "certificate".to_string();
+            links.push(certificate_link);
+        }
+    }
+
+    // A directory node cannot hold two entries with the same name
+    let mut names = HashSet::new();
+    if let Some(link) = links.iter().find(|link| !names.insert(link.name.as_str())) {
+        return Err(Error::validation(
+            folder_path,
+            format!("More than one entry would be named {} in the CAR root", link.name),
+        ));
+    }
+
+    let file_count = links.len();
+    let root = ipfs::build_directory_node(links, &mut |cid, block| car.borrow_mut().write_block(cid, block))?;
+
+    let car = car.into_inner();
+    let block_count = car.written.len();
+    let mut file = car.writer.into_inner().map_err(|e| e.into_error()).with_path(output_path)?;
+    file.seek(SeekFrom::Start(0)).with_path(output_path)?;
+    file.write_all(&encode_header(&root.cid)).with_path(output_path)?;
+    file.flush().with_path(output_path)?;
+    let car_size = file.seek(SeekFrom::End(0)).with_path(output_path)?;
+
+    Ok(CarExport {
+        root_cid: root.cid.to_string(),
+        file_count,
+        block_count,
+        car_size,
+    })
+}
+
+/// Records the CAR root CID in an existing metadata file
+/// The CAR itself contains the metadata as it was before this update
+pub fn write_root_cid(metadata_path: &Path, root_cid: &str) -> Result<()> {
+    let mut metadata = read_metadata_file(metadata_path)?;
+    metadata.ipfs_root_cid = Some(root_cid.to_string());
+    write_metadata(metadata_path, &metadata)
+}
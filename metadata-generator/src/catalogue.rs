@@ -0,0 +1,379 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use crate::constants::user_data_dir;
+use crate::error::{Error, IoResultExt, Result};
+use crate::metadata_generator::{read_metadata_file, VerificationReport};
+use crate::time::utc_now;
+
+/// Bumped whenever the tables change, so `migrate` can tell older databases apart
+const SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS editions (
+        id INTEGER PRIMARY KEY,
+        metadata_path TEXT NOT NULL UNIQUE,
+        base_folder TEXT NOT NULL,
+        metadata_file_hash TEXT NOT NULL,
+        artwork_id TEXT NOT NULL,
+        artwork_title TEXT NOT NULL,
+        artwork_short_title TEXT NOT NULL,
+        artwork_creator TEXT NOT NULL,
+        year_of_creation INTEGER NOT NULL,
+        edition_number INTEGER NOT NULL,
+        total_editions INTEGER NOT NULL,
+        gallery TEXT NOT NULL,
+        recorded_at TEXT NOT NULL,
+        last_verified_at TEXT,
+        last_verification_valid INTEGER,
+        last_valid_files INTEGER,
+        last_invalid_files INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS keywords (
+        edition_id INTEGER NOT NULL REFERENCES editions(id) ON DELETE CASCADE,
+        keyword TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS files (
+        edition_id INTEGER NOT NULL REFERENCES editions(id) ON DELETE CASCADE,
+        file_name TEXT NOT NULL,
+        file_hash TEXT NOT NULL,
+        file_size INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS keywords_edition ON keywords(edition_id);
+    CREATE INDEX IF NOT EXISTS files_edition ON files(edition_id);
+    CREATE INDEX IF NOT EXISTS files_hash ON files(file_hash);
+";
+
+/// Search terms; every term that is set must match, and text terms match anywhere, ignoring case
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CatalogueQuery {
+    /// Matches the title, short title, creator, gallery, artwork ID, a keyword or a file hash
+    pub text: Option<String>,
+    pub title: Option<String>,
+    pub creator: Option<String>,
+    pub keyword: Option<String>,
+    pub gallery: Option<String>,
+    /// Matches the start of an artwork file hash or of the metadata file fingerprint
+    pub file_hash: Option<String>,
+}
+
+/// One metadata document in the catalogue
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CatalogueEntry {
+    pub metadata_path: PathBuf,
+    pub base_folder: PathBuf,
+    pub metadata_file_hash: String,
+    pub artwork_id: String,
+    pub artwork_title: String,
+    pub artwork_creator: String,
+    pub year_of_creation: i32,
+    pub edition_number: i32,
+    pub total_editions: i32,
+    pub gallery: String,
+    pub keywords: Vec<String>,
+    pub file_count: usize,
+    /// When the document was last generated, verified or scanned
+    pub recorded_at: String,
+    /// Cleared when the metadata file changes, since the old result no longer applies
+    pub last_verified_at: Option<String>,
+    pub last_verification_valid: Option<bool>,
+}
+
+/// Outcome of scanning drives for metadata files
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ScanSummary {
+    pub recorded: usize,
+    /// Files named like metadata that could not be read or parsed
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Embedded SQLite index of every metadata document generated, verified or scanned on this machine
+pub struct Catalogue {
+    connection: Connection,
+    path: PathBuf,
+}
+
+impl Catalogue {
+    /// Catalogue shared by the CLI and the GUI of the current user
+    pub fn default_path() -> Option<PathBuf> {
+        user_data_dir().map(|data_dir| data_dir.join("catalogue.sqlite3"))
+    }
+
+    /// Opens the catalogue in the user data directory
+    pub fn open_default() -> Result<Self> {
+        let path = Self::default_path().ok_or_else(|| {
+            Error::io("catalogue.sqlite3", std::io::Error::new(std::io::ErrorKind::NotFound, "No user data directory found"))
+        })?;
+        Self::open(&path)
+    }
+
+    /// Opens or creates a catalogue
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).with_path(parent)?;
+        }
+        let connection = Connection::open(path).map_err(|e| Error::database(path, e))?;
+        let catalogue = Self { connection, path: path.to_path_buf() };
+        catalogue.migrate()?;
+        Ok(catalogue)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn migrate(&self) -> Result<()> {
+        let version: i64 = self
+            .connection
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .map_err(|e| self.error(e))?;
+        if version > SCHEMA_VERSION {
+            return Err(Error::database(&self.path, "The catalogue was written by a newer version of this tool"));
+        }
+        self.connection
+            .execute_batch(&format!("PRAGMA foreign_keys = ON; {} PRAGMA user_version = {};", SCHEMA, SCHEMA_VERSION))
+            .map_err(|e| self.error(e))
+    }
+
+    fn error(&self, error: rusqlite::Error) -> Error {
+        Error::database(&self.path, error)
+    }
+
+    /// Adds a metadata document, or refreshes it if its path is already catalogued
+    pub fn record(&self, metadata_path: &Path, base_folder: &Path) -> Result<()> {
+        let metadata = read_metadata_file(metadata_path)?;
+        // Same fingerprint as `MetadataGenerator::fingerprint_metadata_file`
+        let metadata_file_hash = blake3::hash(&fs::read(metadata_path).with_path(metadata_path)?).to_hex().to_string();
+        let metadata_path = fs::canonicalize(metadata_path).with_path(metadata_path)?;
+        let base_folder = fs::canonicalize(base_folder).unwrap_or_else(|_| base_folder.to_path_buf());
+
+        let transaction = self.connection.unchecked_transaction().map_err(|e| self.error(e))?;
+        let edition_id: i64 = transaction
+            .query_row(
+                "INSERT INTO editions (metadata_path, base_folder, metadata_file_hash, artwork_id, artwork_title,
+                     artwork_short_title, artwork_creator, year_of_creation, edition_number, total_editions, gallery,
+                     recorded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                 ON CONFLICT (metadata_path) DO UPDATE SET
+                     base_folder = excluded.base_folder,
+                     metadata_file_hash = excluded.metadata_file_hash,
+                     artwork_id = excluded.artwork_id,
+                     artwork_title = excluded.artwork_title,
+                     artwork_short_title = excluded.artwork_short_title,
+                     artwork_creator = excluded.artwork_creator,
+                     year_of_creation = excluded.year_of_creation,
+                     edition_number = excluded.edition_number,
+                     total_editions = excluded.total_editions,
+                     gallery = excluded.gallery,
+                     recorded_at = excluded.recorded_at,
+                     last_verified_at = CASE WHEN metadata_file_hash = excluded.metadata_file_hash
+                         THEN last_verified_at END,
+                     last_verification_valid = CASE WHEN metadata_file_hash = excluded.metadata_file_hash
+                         THEN last_verification_valid END,
+                     last_valid_files = CASE WHEN metadata_file_hash = excluded.metadata_file_hash
+                         THEN last_valid_files END,
+                     last_invalid_files = CASE WHEN metadata_file_hash = excluded.metadata_file_hash
+                         THEN last_invalid_files END
+                 RETURNING id",
+                params![
+                    metadata_path.to_string_lossy(),
+                    base_folder.to_string_lossy(),
+                    metadata_file_hash,
+                    metadata.artwork_id,
+                    metadata.artwork_title,
+                    metadata.artwork_short_title,
+                    metadata.artwork_creator,
+                    metadata.year_of_creation,
+                    metadata.edition_number,
+                    metadata.total_editions,
+                    metadata.gallery,
+                    utc_now(),
+                ],
+                |row| row.get(0),
+            )
+            .map_err(|e| self.error(e))?;
+
+        transaction.execute("DELETE FROM keywords WHERE edition_id = ?1", [edition_id]).map_err(|e| self.error(e))?;
+        transaction.execute("DELETE FROM files WHERE edition_id = ?1", [edition_id]).map_err(|e| self.error(e))?;
+        for keyword in &metadata.keywords {
+            transaction
+                .execute("INSERT INTO keywords (edition_id, keyword) VALUES (?1, ?2)", params![edition_id, keyword])
+                .map_err(|e| self.error(e))?;
+        }
+        for artwork_file in &metadata.artwork_files {
+            transaction
+                .execute(
+                    "INSERT INTO files (edition_id, file_name, file_hash, file_size) VALUES (?1, ?2, ?3, ?4)",
+                    params![edition_id, artwork_file.file_name, artwork_file.file_hash, artwork_file.file_size as i64],
+                )
+                .map_err(|e| self.error(e))?;
+        }
+        transaction.commit().map_err(|e| self.error(e))
+    }
+
+    /// Records a metadata document together with the outcome of verifying it
+    pub fn record_verification(&self, metadata_path: &Path, base_folder: &Path, report: &VerificationReport) -> Result<()> {
+        self.record(metadata_path, base_folder)?;
+        let metadata_path = fs::canonicalize(metadata_path).with_path(metadata_path)?;
+        self.connection
+            .execute(
+                "UPDATE editions SET last_verified_at = ?2, last_verification_valid = ?3, last_valid_files = ?4,
+                     last_invalid_files = ?5
+                 WHERE metadata_path = ?1",
+                params![
+                    metadata_path.to_string_lossy(),
+                    utc_now(),
+                    report.overall_valid,
+                    report.valid_files as i64,
+                    report.invalid_files as i64,
+                ],
+            )
+            .map_err(|e| self.error(e))?;
+        Ok(())
+    }
+
+    /// Finds every `*_metadata.json` below a folder, e.g. a whole archive drive, and records it
+    /// with its own folder as the base folder
+    pub fn scan(&self, folder: &Path) -> Result<ScanSummary> {
+        let mut metadata_paths = Vec::new();
+        find_metadata_files(folder, &mut metadata_paths).with_path(folder)?;
+        metadata_paths.sort();
+
+        let mut summary = ScanSummary::default();
+        for metadata_path in metadata_paths {
+            let base_folder = metadata_path.parent().unwrap_or(folder).to_path_buf();
+            match self.record(&metadata_path, &base_folder) {
+                Ok(()) => summary.recorded += 1,
+                Err(e @ Error::Database { .. }) => return Err(e),
+                Err(_) => summary.skipped.push(metadata_path),
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Drops a metadata document from the catalogue; returns false if it was not catalogued
+    pub fn remove(&self, metadata_path: &Path) -> Result<bool> {
+        let metadata_path = fs::canonicalize(metadata_path).unwrap_or_else(|_| metadata_path.to_path_buf());
+        let removed = self
+            .connection
+            .execute("DELETE FROM editions WHERE metadata_path = ?1", [metadata_path.to_string_lossy()])
+            .map_err(|e| self.error(e))?;
+        Ok(removed > 0)
+    }
+
+    /// Catalogued documents matching the query, by creator, title and edition
+    pub fn search(&self, query: &CatalogueQuery) -> Result<Vec<CatalogueEntry>> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT e.id, e.metadata_path, e.base_folder, e.metadata_file_hash, e.artwork_id, e.artwork_title,
+                     e.artwork_creator, e.year_of_creation, e.edition_number, e.total_editions, e.gallery,
+                     e.recorded_at, e.last_verified_at, e.last_verification_valid,
+                     (SELECT COUNT(*) FROM files f WHERE f.edition_id = e.id)
+                 FROM editions e
+                 WHERE (?1 IS NULL OR e.artwork_title LIKE ?1 ESCAPE '\\' OR e.artwork_short_title LIKE ?1 ESCAPE '\\'
+                         OR e.artwork_creator LIKE ?1 ESCAPE '\\' OR e.gallery LIKE ?1 ESCAPE '\\'
+                         OR e.artwork_id LIKE ?1 ESCAPE '\\' OR e.metadata_file_hash LIKE ?1 ESCAPE '\\'
+                         OR EXISTS (SELECT 1 FROM keywords k WHERE k.edition_id = e.id AND k.keyword LIKE ?1 ESCAPE '\\')
+                         OR EXISTS (SELECT 1 FROM files f WHERE f.edition_id = e.id AND f.file_hash LIKE ?1 ESCAPE '\\'))
+                   AND (?2 IS NULL OR e.artwork_title LIKE ?2 ESCAPE '\\' OR e.artwork_short_title LIKE ?2 ESCAPE '\\')
+                   AND (?3 IS NULL OR e.artwork_creator LIKE ?3 ESCAPE '\\')
+                   AND (?4 IS NULL
+                         OR EXISTS (SELECT 1 FROM keywords k WHERE k.edition_id = e.id AND k.keyword LIKE ?4 ESCAPE '\\'))
+                   AND (?5 IS NULL OR e.gallery LIKE ?5 ESCAPE '\\')
+                   AND (?6 IS NULL OR e.metadata_file_hash LIKE ?6 ESCAPE '\\'
+                         OR EXISTS (SELECT 1 FROM files f WHERE f.edition_id = e.id AND f.file_hash LIKE ?6 ESCAPE '\\'))
+                 ORDER BY e.artwork_creator, e.artwork_title, e.edition_number",
+            )
+            .map_err(|e| self.error(e))?;
+
+        let contains = |term: &Option<String>| term.as_deref().map(|term| format!("%{}%", escape_like(term.trim())));
+        let rows = statement
+            .query_map(
+                params![
+                    contains(&query.text),
+                    contains(&query.title),
+                    contains(&query.creator),
+                    contains(&query.keyword),
+                    contains(&query.gallery),
+                    query.file_hash.as_deref().map(|hash| format!("{}%", escape_like(&hash.trim().to_lowercase()))),
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        CatalogueEntry {
+                            metadata_path: PathBuf::from(row.get::<_, String>(1)?),
+                            base_folder: PathBuf::from(row.get::<_, String>(2)?),
+                            metadata_file_hash: row.get(3)?,
+                            artwork_id: row.get(4)?,
+                            artwork_title: row.get(5)?,
+                            artwork_creator: row.get(6)?,
+                            year_of_creation: row.get(7)?,
+                            edition_number: row.get(8)?,
+                            total_editions: row.get(9)?,
+                            gallery: row.get(10)?,
+                            keywords: Vec::new(),
+                            file_count: row.get::<_, i64>(14)? as usize,
+                            recorded_at: row.get(11)?,
+                            last_verified_at: row.get(12)?,
+                            last_verification_valid: row.get(13)?,
+                        },
+                    ))
+                },
+            )
+            .map_err(|e| self.error(e))?;
+
+        let mut keyword_statement = self
+            .connection
+            .prepare("SELECT keyword FROM keywords WHERE edition_id = ?1 ORDER BY rowid")
+            .map_err(|e| self.error(e))?;
+        let mut entries = Vec::new();
+        for row in rows {
+            let (edition_id, mut entry) = row.map_err(|e| self.error(e))?;
+            entry.keywords = keyword_statement
+                .query_map([edition_id], |row| row.get(0))
+                .and_then(|keywords| keywords.collect::<rusqlite::Result<Vec<String>>>())
+                .map_err(|e| self.error(e))?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
+
+/// Adds a generated or verified metadata file to the catalogue shared by every run of the current user
+/// A verification report also records when the edition was last verified and whether it was intact
+pub fn record_in_default_catalogue(metadata_path: &Path, base_folder: &Path, report: Option<&VerificationReport>) -> Result<()> {
+    let catalogue = Catalogue::open_default()?;
+    match report {
+        Some(report) => catalogue.record_verification(metadata_path, base_folder, report),
+        None => catalogue.record(metadata_path, base_folder),
+    }
+}
+
+/// Escapes the LIKE wildcards in a search term so `_` and `%` match literally
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Collects metadata files below a folder; folders that cannot be read, e.g. system folders on a
+/// drive root, are skipped
+fn find_metadata_files(folder: &Path, metadata_paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(folder)? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            let _ = find_metadata_files(&path, metadata_paths);
+        } else if file_type.is_file() && path.file_name().is_some_and(|name| name.to_string_lossy().ends_with("_metadata.json")) {
+            metadata_paths.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,235 @@
+use std::{fs, path::Path};
+use qrcode::{Color, EcLevel, QrCode};
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, IoResultExt, Result};
+use crate::metadata_generator::{read_metadata_file, Metadata};
+use crate::xml::escape_xml;
+
+/// Version of the QR payload format, bumped if the encoded fields change
+pub const QR_PAYLOAD_VERSION: u32 = 1;
+
+/// Pixels per QR module when rendering PNG images
+const PNG_MODULE_SIZE: u32 = 8;
+
+/// Number of light modules around the code required by the QR specification
+const QUIET_ZONE: u32 = 4;
+
+/// Data encoded in the QR code printed on a physical certificate
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QrPayload {
+    #[serde(rename = "v")]
+    pub version: u32,
+    #[serde(rename = "id")]
+    pub artwork_id: String,
+    #[serde(rename = "ed")]
+    pub edition_number: i32,
+    #[serde(rename = "h")]
+    pub metadata_hash: String,
+}
+
+/// Result of checking a decoded QR payload against a metadata file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QrVerification {
+    pub artwork_id_matches: bool,
+    pub edition_number_matches: bool,
+    pub metadata_hash_matches: bool,
+    pub expected_metadata_hash: String,
+    pub actual_metadata_hash: String,
+    pub is_valid: bool,
+}
+
+impl QrVerification {
+    /// Turns a mismatch into `Error::Signature` for callers that only need pass or fail
+    pub fn ensure_valid(&self, metadata_path: &Path) -> Result<()> {
+        if self.is_valid {
+            return Ok(());
+        }
+        Err(Error::signature(
+            metadata_path,
+            format!(
+                "Certificate QR code does not match (expected metadata hash {}, actual {})",
+                self.expected_metadata_hash, self.actual_metadata_hash
+            ),
+        ))
+    }
+}
+
+impl QrPayload {
+    pub fn from_metadata(metadata: &Metadata) -> Result<Self> {
+        Ok(Self {
+            version: QR_PAYLOAD_VERSION,
+            artwork_id: metadata.artwork_id.clone(),
+            edition_number: metadata.edition_number,
+            metadata_hash: metadata.canonical_hash()?,
+        })
+    }
+
+    pub fn from_metadata_file(metadata_path: &Path) -> Result<Self> {
+        Self::from_metadata(&read_metadata_file(metadata_path)?)
+    }
+
+    /// Encodes the payload as compact JSON, which is what gets stored in the QR code
+    pub fn encode(&self) -> String {
+        serde_json::to_string(self).expect("QR payload serialization cannot fail")
+    }
+
+    /// Parses the text decoded from a QR code by any scanner app
+    pub fn decode(payload: &str) -> Result<Self> {
+        let decoded: Self = serde_json::from_str(payload.trim())
+            .map_err(|e| Error::Parse { path: None, message: format!("Not a certificate QR code: {}", e) })?;
+        if decoded.version != QR_PAYLOAD_VERSION {
+            return Err(Error::Parse {
+                path: None,
+                message: format!("Unsupported QR payload version: {}", decoded.version),
+            });
+        }
+        Ok(decoded)
+    }
+
+    fn to_qr_code(&self) -> Result<QrCode> {
+        // Medium error correction survives light wear on printed certificates
+        QrCode::with_error_correction_level(self.encode(), EcLevel::M)
+            .map_err(|e| Error::Validation { path: None, message: format!("Cannot encode QR code: {}", e) })
+    }
+
+    /// Builds an SVG path covering the dark modules, offset by the quiet zone
+    /// Returns the path data and the total side length in modules
+    fn svg_path(&self) -> Result<(String, u32)> {
+        let code = self.to_qr_code()?;
+        let width = code.width() as u32;
+        let mut path = String::new();
+        for (index, color) in code.to_colors().iter().enumerate() {
+            if *color == Color::Dark {
+                let x = index as u32 % width + QUIET_ZONE;
+                let y = index as u32 / width + QUIET_ZONE;
+                path.push_str(&format!("M{} {}h1v1h-1z", x, y));
+            }
+        }
+        Ok((path, width + QUIET_ZONE * 2))
+    }
+
+    /// Renders the QR code as an SVG element positioned at (x, y) with the given size,
+    /// suitable for nesting inside a larger SVG document
+    pub fn to_svg_element(&self, x: f32, y: f32, size: f32) -> Result<String> {
+        let (path, modules) = self.svg_path()?;
+        Ok(format!(
+            r##"<svg x="{x}" y="{y}" width="{size}" height="{size}" viewBox="0 0 {modules} {modules}" shape-rendering="crispEdges"><rect width="{modules}" height="{modules}" fill="#ffffff"/><path d="{path}" fill="#000000"/></svg>"##
+        ))
+    }
+
+    /// Renders the QR code as a standalone SVG document
+    pub fn to_svg(&self) -> Result<String> {
+        let (path, modules) = self.svg_path()?;
+        let size = modules * PNG_MODULE_SIZE;
+        Ok(format!(
+            r##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {modules} {modules}" shape-rendering="crispEdges">
+<rect width="{modules}" height="{modules}" fill="#ffffff"/>
+<path d="{path}" fill="#000000"/>
+</svg>
+"##
+        ))
+    }
+
+    /// Renders the QR code as a PNG image
+    pub fn write_png(&self, output_path: &Path) -> Result<()> {
+        let code = self.to_qr_code()?;
+        let width = code.width() as u32;
+        let colors = code.to_colors();
+        let image_size = (width + QUIET_ZONE * 2) * PNG_MODULE_SIZE;
+
+        let image = image::GrayImage::from_fn(image_size, image_size, |x, y| {
+            let module_x = (x / PNG_MODULE_SIZE) as i64 - QUIET_ZONE as i64;
+            let module_y = (y / PNG_MODULE_SIZE) as i64 - QUIET_ZONE as i64;
+            let is_dark = module_x >= 0
+                && module_y >= 0
+                && (module_x as u32) < width
+                && (module_y as u32) < width
+                && colors[(module_y as u32 * width + module_x as u32) as usize] == Color::Dark;
+            image::Luma([if is_dark { 0 } else { 255 }])
+        });
+
+        image
+            .save_with_format(output_path, image::ImageFormat::Png)
+            .map_err(|e| Error::io(output_path, std::io::Error::other(e.to_string())))
+    }
+
+    /// Writes the QR code as PNG or SVG depending on the output file extension
+    pub fn write_to_file(&self, output_path: &Path) -> Result<()> {
+        let extension = output_path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "svg" => fs::write(output_path, self.to_svg()?).with_path(output_path),
+            "png" => self.write_png(output_path),
+            _ => Err(Error::validation(output_path, "QR code output must have a .png or .svg extension")),
+        }
+    }
+}
+
+/// Generates a printable A4 certificate page as SVG with the QR code embedded
+pub fn generate_certificate_svg(metadata: &Metadata) -> Result<String> {
+    let payload = QrPayload::from_metadata(metadata)?;
+    let qr_svg = payload.to_svg_element(75.0, 170.0, 60.0)?;
+
+    let mut lines = vec![
+        r#"<text x="105" y="40" font-size="10" text-anchor="middle">Certificate of Authenticity</text>"#.to_string(),
+        format!(
+            r#"<text x="105" y="70" font-size="14" font-weight="bold" text-anchor="middle">{}</text>"#,
+            escape_xml(&metadata.artwork_title)
+        ),
+        format!(
+            r#"<text x="105" y="82" font-size="7" text-anchor="middle">{}, {}</text>"#,
+            escape_xml(&metadata.artwork_creator),
+            metadata.year_of_creation
+        ),
+        format!(
+            r#"<text x="105" y="100" font-size="6" text-anchor="middle">Edition {} of {}</text>"#,
+            metadata.edition_number, metadata.total_editions
+        ),
+    ];
+    if !metadata.gallery.is_empty() {
+        lines.push(format!(
+            r#"<text x="105" y="110" font-size="5" text-anchor="middle">{}</text>"#,
+            escape_xml(&metadata.gallery)
+        ));
+    }
+    lines.push(format!(
+        r#"<text x="105" y="260" font-size="3" text-anchor="middle" font-family="monospace">Artwork ID: {} / Metadata: {}</text>"#,
+        escape_xml(&metadata.artwork_id),
+        payload.metadata_hash
+    ));
+
+    Ok(format!(
+        r##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="210mm" height="297mm" viewBox="0 0 210 297" font-family="serif">
+<rect width="210" height="297" fill="#ffffff"/>
+{}
+{}
+</svg>
+"##,
+        lines.join("\n"),
+        qr_svg
+    ))
+}
+
+/// Checks a decoded QR payload against the given metadata file
+pub fn verify_qr_payload(payload: &str, metadata_path: &Path) -> Result<QrVerification> {
+    let payload = QrPayload::decode(payload)?;
+    let actual = QrPayload::from_metadata_file(metadata_path)?;
+
+    let artwork_id_matches = payload.artwork_id == actual.artwork_id;
+    let edition_number_matches = payload.edition_number == actual.edition_number;
+    let metadata_hash_matches = payload.metadata_hash == actual.metadata_hash;
+
+    Ok(QrVerification {
+        artwork_id_matches,
+        edition_number_matches,
+        metadata_hash_matches,
+        expected_metadata_hash: payload.metadata_hash,
+        actual_metadata_hash: actual.metadata_hash,
+        is_valid: artwork_id_matches && edition_number_matches && metadata_hash_matches,
+    })
+}
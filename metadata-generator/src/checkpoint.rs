@@ -0,0 +1,103 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+use serde::{Deserialize, Serialize};
+use crate::constants::VERIFY_CHECKPOINT_EXTENSION;
+use crate::error::{Error, IoResultExt, Result};
+use crate::hash_cache::{file_stamp, FileStamp};
+
+const CHECKPOINT_VERSION: u32 = 1;
+
+/// A file whose hash was read before the run stopped, with the identity it had at the time
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CheckpointEntry {
+    #[serde(flatten)]
+    stamp: FileStamp,
+    file_hash: String,
+}
+
+/// Per-file progress of a verification, so an interrupted run over a large or flaky volume can
+/// pick up where it stopped instead of re-reading everything
+/// Only files that were read completely are recorded; files that failed are read again on resume
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VerificationCheckpoint {
+    version: u32,
+    metadata_file_hash: String,
+    base_folder: PathBuf,
+    entries: HashMap<String, CheckpointEntry>,
+}
+
+/// The same folder given as `.` or as an absolute path must resume the same checkpoint
+fn canonical_folder(folder: &Path) -> PathBuf {
+    fs::canonicalize(folder).unwrap_or_else(|_| folder.to_path_buf())
+}
+
+impl VerificationCheckpoint {
+    /// Checkpoint kept next to the metadata file, e.g. `Title_metadata.verify-checkpoint.json`
+    pub fn path_for(metadata_path: &Path) -> PathBuf {
+        metadata_path.with_extension(VERIFY_CHECKPOINT_EXTENSION)
+    }
+
+    pub fn new(metadata_file_hash: &str, base_folder: &Path) -> Self {
+        Self {
+            version: CHECKPOINT_VERSION,
+            metadata_file_hash: metadata_file_hash.to_string(),
+            base_folder: canonical_folder(base_folder),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Loads the checkpoint left by an earlier run of the same metadata file against the same folder,
+    /// starting afresh if there is none or it belongs to something else
+    pub fn load(checkpoint_path: &Path, metadata_file_hash: &str, base_folder: &Path) -> Self {
+        fs::read_to_string(checkpoint_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|checkpoint| {
+                checkpoint.version == CHECKPOINT_VERSION
+                    && checkpoint.metadata_file_hash == metadata_file_hash
+                    && checkpoint.base_folder == canonical_folder(base_folder)
+            })
+            .unwrap_or_else(|| Self::new(metadata_file_hash, base_folder))
+    }
+
+    /// Number of files already read
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the recorded hash if the file's size, mtime and inode are unchanged since it was read
+    pub fn completed_hash(&self, file_name: &str, file_path: &Path) -> Option<String> {
+        let entry = self.entries.get(file_name)?;
+        let stamp = file_stamp(file_path).ok()?;
+        (entry.stamp == stamp).then(|| entry.file_hash.clone())
+    }
+
+    pub fn record(&mut self, file_name: &str, file_path: &Path, file_hash: String) {
+        if let Ok(stamp) = file_stamp(file_path) {
+            self.entries.insert(file_name.to_string(), CheckpointEntry { stamp, file_hash });
+        }
+    }
+
+    /// Writes the checkpoint through a temporary file so an interrupted save never leaves it truncated
+    pub fn save(&self, checkpoint_path: &Path) -> Result<()> {
+        let temporary_path = checkpoint_path.with_extension("json.tmp");
+        let content = serde_json::to_vec(self).map_err(|e| Error::io(checkpoint_path, e.into()))?;
+        fs::write(&temporary_path, content).with_path(&temporary_path)?;
+        fs::rename(&temporary_path, checkpoint_path).with_path(checkpoint_path)
+    }
+
+    /// Removes the checkpoint once a run has completed
+    pub fn remove(checkpoint_path: &Path) -> Result<()> {
+        match fs::remove_file(checkpoint_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(Error::io(checkpoint_path, e)),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use crate::error::{Error, IoResultExt, Result};
+use crate::hasher::UnifiedHasher;
+use crate::events::{FileOutcome, ProgressEvent};
+use crate::metadata_generator::{Metadata, MetadataGenerator};
+
+/// Algorithms found in `*sum`-style checksum manifests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+/// Line format of a checksum manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestStyle {
+    /// `<hash>  <file>` as written by `sha256sum`, `md5sum` and `b3sum`
+    Gnu,
+    /// `SHA256 (<file>) = <hash>` as written by BSD `sha256`/`md5` and `shasum --tag`
+    Bsd,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecksumEntry {
+    pub path: String,
+    pub algorithm: ChecksumAlgorithm,
+    pub hash: String,
+}
+
+impl ChecksumAlgorithm {
+    pub const ALL: [ChecksumAlgorithm; 5] = [
+        ChecksumAlgorithm::Md5,
+        ChecksumAlgorithm::Sha1,
+        ChecksumAlgorithm::Sha256,
+        ChecksumAlgorithm::Sha512,
+        ChecksumAlgorithm::Blake3,
+    ];
+
+    /// Parses names such as `sha256`, `SHA-256`, `md5` or `b3`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace('-', "").as_str() {
+            "md5" => Some(ChecksumAlgorithm::Md5),
+            "sha1" => Some(ChecksumAlgorithm::Sha1),
+            "sha256" => Some(ChecksumAlgorithm::Sha256),
+            "sha512" => Some(ChecksumAlgorithm::Sha512),
+            "blake3" | "b3" => Some(ChecksumAlgorithm::Blake3),
+            _ => None,
+        }
+    }
+
+    /// Tag used in BSD-style lines
+    pub fn bsd_tag(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Md5 => "MD5",
+            ChecksumAlgorithm::Sha1 => "SHA1",
+            ChecksumAlgorithm::Sha256 => "SHA256",
+            ChecksumAlgorithm::Sha512 => "SHA512",
+            ChecksumAlgorithm::Blake3 => "BLAKE3",
+        }
+    }
+
+    /// Conventional manifest file name, e.g. `SHA256SUMS`
+    pub fn default_file_name(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Md5 => "MD5SUMS",
+            ChecksumAlgorithm::Sha1 => "SHA1SUMS",
+            ChecksumAlgorithm::Sha256 => "SHA256SUMS",
+            ChecksumAlgorithm::Sha512 => "SHA512SUMS",
+            ChecksumAlgorithm::Blake3 => "B3SUMS",
+        }
+    }
+
+    /// Guesses the algorithm from a manifest file name such as `SHA256SUMS`, `files.md5` or `art.sha512`
+    pub fn from_file_name(path: &Path) -> Option<Self> {
+        if let Some(algorithm) = path.extension().and_then(|extension| Self::from_name(&extension.to_string_lossy())) {
+            return Some(algorithm);
+        }
+        let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+        let stem = file_name.trim_end_matches(".txt").trim_end_matches("sums").trim_end_matches("sum");
+        Self::from_name(stem)
+    }
+
+    /// Length of the hex digest; BLAKE3 and SHA-256 share 64, so this is only a fallback
+    fn from_hex_len(length: usize) -> Option<Self> {
+        match length {
+            32 => Some(ChecksumAlgorithm::Md5),
+            40 => Some(ChecksumAlgorithm::Sha1),
+            64 => Some(ChecksumAlgorithm::Sha256),
+            128 => Some(ChecksumAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    /// Hashes a file with the hasher's read options and cancellation token
+    pub fn hash_file(&self, path: &Path, hasher: &UnifiedHasher) -> Result<String> {
+        self.hash_file_with_progress(path, hasher, &mut |_, _| {})
+    }
+
+    /// `hash_file` calling `on_progress(bytes_hashed, file_size)` as the file is read
+    pub fn hash_file_with_progress(
+        &self,
+        path: &Path,
+        hasher: &UnifiedHasher,
+        on_progress: &mut dyn FnMut(u64, u64),
+    ) -> Result<String> {
+        match self {
+            ChecksumAlgorithm::Md5 => hasher.digest_file_with_progress::<Md5>(path, on_progress),
+            ChecksumAlgorithm::Sha1 => hasher.digest_file_with_progress::<Sha1>(path, on_progress),
+            ChecksumAlgorithm::Sha256 => hasher.digest_file_with_progress::<Sha256>(path, on_progress),
+            ChecksumAlgorithm::Sha512 => hasher.digest_file_with_progress::<Sha512>(path, on_progress),
+            ChecksumAlgorithm::Blake3 => hasher.hash_file_with_progress(&path.to_string_lossy(), on_progress),
+        }
+    }
+}
+
+/// GNU coreutils escapes backslashes and newlines and marks such lines with a leading backslash
+fn escape_gnu_path(path: &str) -> (bool, String) {
+    let escaped = path.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r");
+    (escaped != path, escaped)
+}
+
+fn unescape_gnu_path(path: &str) -> String {
+    let mut unescaped = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+fn is_hex(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn parse_line(line: &str, default_algorithm: Option<ChecksumAlgorithm>) -> Option<ChecksumEntry> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let unescape = |path: &str| if escaped { unescape_gnu_path(path) } else { path.to_string() };
+
+    // BSD style: `SHA256 (file) = hash`
+    if let Some((tag_and_path, hash)) = line.rsplit_once(") = ") {
+        if let Some((tag, path)) = tag_and_path.split_once(" (") {
+            if let Some(algorithm) = ChecksumAlgorithm::from_name(tag.trim()) {
+                if is_hex(hash.trim()) {
+                    return Some(ChecksumEntry {
+                        path: unescape(path),
+                        algorithm,
+                        hash: hash.trim().to_lowercase(),
+                    });
+                }
+            }
+        }
+    }
+
+    // GNU style: `hash  file` (text mode) or `hash *file` (binary mode)
+    let (hash, rest) = line.split_once(' ')?;
+    if !is_hex(hash) {
+        return None;
+    }
+    let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*')).unwrap_or(rest);
+    let algorithm = default_algorithm.or_else(|| ChecksumAlgorithm::from_hex_len(hash.len()))?;
+    Some(ChecksumEntry {
+        path: unescape(path),
+        algorithm,
+        hash: hash.to_lowercase(),
+    })
+}
+
+/// Parses GNU and BSD-style lines, which may be mixed; blank lines and `#` comments are skipped
+/// `default_algorithm` decides GNU lines, whose algorithm is otherwise guessed from the digest length
+pub fn parse_manifest(content: &str, default_algorithm: Option<ChecksumAlgorithm>) -> Result<Vec<ChecksumEntry>> {
+    let mut entries = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = parse_line(line, default_algorithm).ok_or_else(|| Error::Parse {
+            path: None,
+            message: format!("Unrecognised checksum line {}: {}", index + 1, line),
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Reads a checksum manifest, taking the GNU-style algorithm from its file name when possible
+pub fn read_manifest(manifest_path: &Path) -> Result<Vec<ChecksumEntry>> {
+    let content = fs::read_to_string(manifest_path).with_path(manifest_path)?;
+    parse_manifest(&content, ChecksumAlgorithm::from_file_name(manifest_path))
+        .map_err(|e| Error::parse(manifest_path, e.message()))
+}
+
+/// Whether a verification source is a checksum manifest rather than our metadata JSON: named like one
+/// (`SHA256SUMS`, `files.md5`), or a non-JSON file whose first entry line parses as a checksum line
+pub fn is_checksum_manifest(path: &Path) -> bool {
+    if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json")) {
+        return false;
+    }
+    if ChecksumAlgorithm::from_file_name(path).is_some() {
+        return true;
+    }
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    // Only the start of the file is read, so pointing at a large binary file stays cheap
+    BufReader::new(file.take(64 * 1024))
+        .lines()
+        .map_while(|line| line.ok())
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .find(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .is_some_and(|line| parse_line(&line, None).is_some())
+}
+
+pub fn format_entry(entry: &ChecksumEntry, style: ManifestStyle) -> String {
+    let (escaped, path) = escape_gnu_path(&entry.path);
+    let prefix = if escaped { "\\" } else { "" };
+    match style {
+        ManifestStyle::Gnu => format!("{}{}  {}\n", prefix, entry.hash, path),
+        ManifestStyle::Bsd => format!("{}{} ({}) = {}\n", prefix, entry.algorithm.bsd_tag(), path, entry.hash),
+    }
+}
+
+/// Lists the artwork files (and certificate) of an edition with the given algorithm
+/// BLAKE3 reuses the recorded hashes; other algorithms hash the files found in `base_folder` through the
+/// generator, which reports them to its observer
+pub fn manifest_entries(
+    generator: &MetadataGenerator,
+    metadata: &Metadata,
+    base_folder: &Path,
+    algorithm: ChecksumAlgorithm,
+) -> Result<Vec<ChecksumEntry>> {
+    let mut recorded: Vec<(String, Option<String>)> = metadata
+        .artwork_files
+        .iter()
+        .map(|artwork_file| (artwork_file.file_name.clone(), Some(artwork_file.file_hash.clone())))
+        .collect();
+    if let Some(certificate_path) = &metadata.certificate_of_authenticity {
+        recorded.push((certificate_path.trim_start_matches("./").to_string(), metadata.certificate_hash.clone()));
+    }
+
+    let mut entries = Vec::new();
+    let total_files = recorded.len();
+    generator.emit(ProgressEvent::ScanStarted { total_files });
+    for (index, (path, recorded_hash)) in recorded.into_iter().enumerate() {
+        generator.emit(ProgressEvent::FileStarted { file_name: path.clone(), index, total_files });
+        let hash = match (algorithm, recorded_hash) {
+            (ChecksumAlgorithm::Blake3, Some(recorded_hash)) => recorded_hash,
+            _ => generator.hash_with_retries(algorithm, &base_folder.join(&path), &path, index, total_files)?,
+        };
+        generator.emit(ProgressEvent::FileCompleted { file_name: path.clone(), index, total_files, outcome: FileOutcome::Hashed });
+        entries.push(ChecksumEntry { path, algorithm, hash });
+    }
+    generator.emit(ProgressEvent::Finished { processed_files: total_files, failed_files: 0 });
+    Ok(entries)
+}
+
+/// Writes the edition's files as a GNU or BSD-style checksum manifest and returns the number of entries
+pub fn write_manifest(
+    generator: &MetadataGenerator,
+    metadata: &Metadata,
+    base_folder: &Path,
+    algorithm: ChecksumAlgorithm,
+    style: ManifestStyle,
+    output_path: &Path,
+) -> Result<usize> {
+    let entries = manifest_entries(generator, metadata, base_folder, algorithm)?;
+    let manifest: String = entries.iter().map(|entry| format_entry(entry, style)).collect();
+    fs::write(output_path, manifest).with_path(output_path)?;
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA256_EMPTY: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+    const MD5_EMPTY: &str = "d41d8cd98f00b204e9800998ecf8427e";
+
+    fn entry(path: &str, algorithm: ChecksumAlgorithm, hash: &str) -> ChecksumEntry {
+        ChecksumEntry {
+            path: path.to_string(),
+            algorithm,
+            hash: hash.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_gnu_lines() {
+        let content = format!("# comment\n{SHA256_EMPTY}  art/a.png\r\n\n{MD5_EMPTY} *b c.txt\n");
+        assert_eq!(
+            parse_manifest(&content, None).unwrap(),
+            vec![
+                entry("art/a.png", ChecksumAlgorithm::Sha256, SHA256_EMPTY),
+                entry("b c.txt", ChecksumAlgorithm::Md5, MD5_EMPTY),
+            ]
+        );
+
+        // A 64-digit hash is only BLAKE3 when the manifest name says so
+        let content = format!("{SHA256_EMPTY}  a.png\n");
+        assert_eq!(
+            parse_manifest(&content, Some(ChecksumAlgorithm::Blake3)).unwrap(),
+            vec![entry("a.png", ChecksumAlgorithm::Blake3, SHA256_EMPTY)]
+        );
+    }
+
+    #[test]
+    fn parses_bsd_lines() {
+        let upper = SHA256_EMPTY.to_uppercase();
+        let content = format!("SHA256 (art/a (1).png) = {upper}\nMD5 (b.txt) = {MD5_EMPTY}\n");
+        assert_eq!(
+            parse_manifest(&content, None).unwrap(),
+            vec![
+                entry("art/a (1).png", ChecksumAlgorithm::Sha256, SHA256_EMPTY),
+                entry("b.txt", ChecksumAlgorithm::Md5, MD5_EMPTY),
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_lines_round_trip() {
+        let original = entry("dir\\new\nline.txt", ChecksumAlgorithm::Sha256, SHA256_EMPTY);
+        for style in [ManifestStyle::Gnu, ManifestStyle::Bsd] {
+            let line = format_entry(&original, style);
+            assert!(line.starts_with('\\'));
+            assert_eq!(parse_manifest(&line, None).unwrap(), vec![original.clone()]);
+        }
+
+        let content = format!("\\{SHA256_EMPTY}  a\\\\b\\nc\n");
+        assert_eq!(
+            parse_manifest(&content, None).unwrap(),
+            vec![entry("a\\b\nc", ChecksumAlgorithm::Sha256, SHA256_EMPTY)]
+        );
+    }
+
+    #[test]
+    fn rejects_unrecognised_lines() {
+        let error = parse_manifest(&format!("{SHA256_EMPTY}  a.png\nnot a checksum\n"), None).unwrap_err();
+        assert!(error.message().contains("line 2"));
+    }
+}
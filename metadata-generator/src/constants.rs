@@ -1,10 +1,42 @@
+use std::path::PathBuf;
+
+/// Per-folder hash cache written next to the artwork files
+pub const HASH_CACHE_FILE: &str = ".metadata_hash_cache.json";
+
+/// Extension of the checkpoint a resumable verification keeps next to the metadata file
+pub const VERIFY_CHECKPOINT_EXTENSION: &str = "verify-checkpoint.json";
+
+/// Extra attempts at reading a file after a transient I/O error, before it is reported as failed
+pub const DEFAULT_READ_RETRIES: u32 = 2;
+
 pub const IGNORE_FILES: &[&str] = &[
-    ".DS_Store", "Thumbs.db", ".gitignore", ".gitkeep"
+    ".DS_Store", "Thumbs.db", ".gitignore", ".gitkeep", HASH_CACHE_FILE
 ];
 
+/// Our folder under a per-user base directory: the XDG variable, then the Windows variable, then
+/// the conventional folder under HOME
+fn user_dir(xdg_var: &str, windows_var: &str, home_relative: &str) -> Option<PathBuf> {
+    let base_dir = std::env::var_os(xdg_var)
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os(windows_var).map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(home_relative)))?;
+    Some(base_dir.join("metadata-generator"))
+}
+
+/// Per-user data directory for state kept across runs, such as the fixity audit log
+pub fn user_data_dir() -> Option<PathBuf> {
+    user_dir("XDG_DATA_HOME", "APPDATA", ".local/share")
+}
+
+/// Per-user cache directory for data that can be rebuilt, such as the shared hash cache
+pub fn user_cache_dir() -> Option<PathBuf> {
+    user_dir("XDG_CACHE_HOME", "LOCALAPPDATA", ".cache")
+}
+
 pub fn should_ignore_file(file_name: &str) -> bool {
     IGNORE_FILES.contains(&file_name) || 
     file_name.ends_with("_metadata.json") ||
+    file_name.ends_with(VERIFY_CHECKPOINT_EXTENSION) ||
     file_name.ends_with("metadata.json") ||
     file_name == "metadata.json"
 } 
\ No newline at end of file
@@ -0,0 +1,311 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use crate::error::Result;
+use crate::metadata_generator::{read_metadata_file, ArtworkFile, Metadata};
+
+/// A top-level `Metadata` field whose value differs
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FieldChange {
+    pub field: String,
+    pub old_value: Value,
+    pub new_value: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Added,
+    Removed,
+    /// Same path, different content hash
+    Rehashed,
+    /// Same content, but the size, format or CID recorded for it differs
+    Modified,
+}
+
+/// An `ArtworkFile` entry that was added, removed or changed, matched by its recorded path
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: FileChangeKind,
+    pub old_file: Option<ArtworkFile>,
+    pub new_file: Option<ArtworkFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MetadataDiff {
+    pub field_changes: Vec<FieldChange>,
+    pub file_changes: Vec<FileChange>,
+    pub unchanged_files: usize,
+}
+
+/// Every field except `artwork_files`, in declaration order
+fn fields(metadata: &Metadata) -> Vec<(&'static str, Value)> {
+    vec![
+        ("artwork_id", json!(metadata.artwork_id)),
+        ("artwork_title", json!(metadata.artwork_title)),
+        ("artwork_short_title", json!(metadata.artwork_short_title)),
+        ("artwork_creator", json!(metadata.artwork_creator)),
+        ("year_of_creation", json!(metadata.year_of_creation)),
+        ("short_description", json!(metadata.short_description)),
+        ("long_description", json!(metadata.long_description)),
+        ("edition_number", json!(metadata.edition_number)),
+        ("total_editions", json!(metadata.total_editions)),
+        ("issue_date", json!(metadata.issue_date)),
+        ("gallery", json!(metadata.gallery)),
+        ("keywords", json!(metadata.keywords)),
+        ("medium", json!(metadata.medium)),
+        ("certificate_of_authenticity", json!(metadata.certificate_of_authenticity)),
+        ("certificate_hash", json!(metadata.certificate_hash)),
+        ("merkle_root", json!(metadata.merkle_root)),
+        ("ipfs_root_cid", json!(metadata.ipfs_root_cid)),
+    ]
+}
+
+/// Compares two metadata records, e.g. two drafts or edition 1 and edition 2
+pub fn diff_metadata(old: &Metadata, new: &Metadata) -> MetadataDiff {
+    let mut diff = MetadataDiff::default();
+
+    for ((field, old_value), (_, new_value)) in fields(old).into_iter().zip(fields(new)) {
+        if old_value != new_value {
+            diff.field_changes.push(FieldChange {
+                field: field.to_string(),
+                old_value,
+                new_value,
+            });
+        }
+    }
+
+    let find = |files: &[ArtworkFile], path: &str| files.iter().find(|artwork_file| artwork_file.path == path).cloned();
+    for old_file in &old.artwork_files {
+        let kind = match find(&new.artwork_files, &old_file.path) {
+            None => Some(FileChangeKind::Removed),
+            Some(new_file) if new_file.file_hash != old_file.file_hash => Some(FileChangeKind::Rehashed),
+            Some(new_file) if new_file.file_size != old_file.file_size
+                || new_file.format != old_file.format
+                || new_file.ipfs_cid != old_file.ipfs_cid => Some(FileChangeKind::Modified),
+            Some(_) => None,
+        };
+        match kind {
+            Some(kind) => diff.file_changes.push(FileChange {
+                path: old_file.path.clone(),
+                kind,
+                old_file: Some(old_file.clone()),
+                new_file: find(&new.artwork_files, &old_file.path),
+            }),
+            None => diff.unchanged_files += 1,
+        }
+    }
+    for new_file in &new.artwork_files {
+        if find(&old.artwork_files, &new_file.path).is_none() {
+            diff.file_changes.push(FileChange {
+                path: new_file.path.clone(),
+                kind: FileChangeKind::Added,
+                old_file: None,
+                new_file: Some(new_file.clone()),
+            });
+        }
+    }
+
+    diff
+}
+
+pub fn diff_metadata_files(old_path: &Path, new_path: &Path) -> Result<MetadataDiff> {
+    let old = read_metadata_file(old_path)?;
+    let new = read_metadata_file(new_path)?;
+    Ok(diff_metadata(&old, &new))
+}
+
+/// Shortens a BLAKE3 hex digest for display
+fn short_hash(hash: &str) -> &str {
+    &hash[..hash.len().min(16)]
+}
+
+/// Compact one-line rendering of a field value, with long text truncated
+pub fn display_value(value: &Value) -> String {
+    let text = match value {
+        Value::Null => "(none)".to_string(),
+        Value::String(text) => format!("{:?}", text),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string).unwrap_or_else(|| item.to_string()))
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    };
+    if text.chars().count() > 80 {
+        format!("{}…", text.chars().take(79).collect::<String>())
+    } else {
+        text
+    }
+}
+
+impl FileChange {
+    /// What changed about the file, e.g. `hash 1a2b… → 3c4d…, size 10 → 12`
+    pub fn description(&self) -> String {
+        match (&self.old_file, &self.new_file) {
+            (Some(old_file), Some(new_file)) => {
+                let mut details = Vec::new();
+                if old_file.file_hash != new_file.file_hash {
+                    details.push(format!("hash {}… → {}…", short_hash(&old_file.file_hash), short_hash(&new_file.file_hash)));
+                }
+                if old_file.file_size != new_file.file_size {
+                    details.push(format!("size {} → {} bytes", old_file.file_size, new_file.file_size));
+                }
+                if old_file.format != new_file.format {
+                    details.push(format!("format {} → {}", old_file.format, new_file.format));
+                }
+                if old_file.ipfs_cid != new_file.ipfs_cid {
+                    details.push(format!(
+                        "CID {} → {}",
+                        old_file.ipfs_cid.as_deref().unwrap_or("(none)"),
+                        new_file.ipfs_cid.as_deref().unwrap_or("(none)")
+                    ));
+                }
+                details.join(", ")
+            }
+            (Some(artwork_file), None) | (None, Some(artwork_file)) => {
+                format!("{} bytes, {}, hash {}…", artwork_file.file_size, artwork_file.format, short_hash(&artwork_file.file_hash))
+            }
+            (None, None) => String::new(),
+        }
+    }
+}
+
+impl MetadataDiff {
+    pub fn is_empty(&self) -> bool {
+        self.field_changes.is_empty() && self.file_changes.is_empty()
+    }
+
+    /// Human-readable report: `~` changed, `+` added, `-` removed
+    pub fn to_text(&self) -> String {
+        if self.is_empty() {
+            return "No differences\n".to_string();
+        }
+
+        let mut text = String::new();
+        if !self.field_changes.is_empty() {
+            text.push_str("Fields:\n");
+            for change in &self.field_changes {
+                text.push_str(&format!(
+                    "  ~ {}: {} → {}\n",
+                    change.field,
+                    display_value(&change.old_value),
+                    display_value(&change.new_value)
+                ));
+            }
+        }
+        if !self.file_changes.is_empty() {
+            text.push_str("Files:\n");
+            for change in &self.file_changes {
+                let marker = match change.kind {
+                    FileChangeKind::Added => '+',
+                    FileChangeKind::Removed => '-',
+                    FileChangeKind::Rehashed | FileChangeKind::Modified => '~',
+                };
+                text.push_str(&format!("  {} {} ({})\n", marker, change.path, change.description()));
+            }
+        }
+        text.push_str(&format!(
+            "{} field(s) changed, {} file(s) changed, {} file(s) unchanged\n",
+            self.field_changes.len(),
+            self.file_changes.len(),
+            self.unchanged_files
+        ));
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn artwork_file(path: &str, file_hash: &str, file_size: u64) -> ArtworkFile {
+        ArtworkFile {
+            path: path.to_string(),
+            file_name: path.rsplit('/').next().unwrap().to_string(),
+            file_hash: file_hash.to_string(),
+            file_size,
+            format: "png".to_string(),
+            ipfs_cid: None,
+        }
+    }
+
+    fn metadata(artwork_files: Vec<ArtworkFile>) -> Metadata {
+        Metadata {
+            artwork_id: "ID".to_string(),
+            artwork_title: "Title".to_string(),
+            artwork_short_title: "T".to_string(),
+            artwork_creator: "Artist".to_string(),
+            year_of_creation: 2024,
+            short_description: String::new(),
+            long_description: String::new(),
+            edition_number: 1,
+            total_editions: 2,
+            issue_date: "2024-01-01".to_string(),
+            gallery: String::new(),
+            keywords: Vec::new(),
+            medium: Vec::new(),
+            certificate_of_authenticity: None,
+            certificate_hash: None,
+            artwork_files,
+            merkle_root: None,
+            ipfs_root_cid: None,
+        }
+    }
+
+    fn write_metadata(name: &str, metadata: &Metadata) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("diff-test-{}-{name}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_string(metadata).unwrap()).unwrap();
+        path
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_files() {
+        let old = metadata(vec![
+            artwork_file("./a.png", "aa", 10),
+            artwork_file("./b.png", "bb", 20),
+            artwork_file("./c.png", "cc", 30),
+            artwork_file("./d.png", "dd", 40),
+        ]);
+        let mut new = metadata(vec![
+            artwork_file("./a.png", "aa", 10),
+            artwork_file("./c.png", "c2", 31),
+            artwork_file("./d.png", "dd", 41),
+            artwork_file("./e.png", "ee", 50),
+        ]);
+        new.edition_number = 2;
+
+        let old_path = write_metadata("old", &old);
+        let new_path = write_metadata("new", &new);
+        let diff = diff_metadata_files(&old_path, &new_path).unwrap();
+        std::fs::remove_file(old_path).unwrap();
+        std::fs::remove_file(new_path).unwrap();
+
+        let changes: Vec<(&str, FileChangeKind)> =
+            diff.file_changes.iter().map(|change| (change.path.as_str(), change.kind)).collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("./b.png", FileChangeKind::Removed),
+                ("./c.png", FileChangeKind::Rehashed),
+                ("./d.png", FileChangeKind::Modified),
+                ("./e.png", FileChangeKind::Added),
+            ]
+        );
+        assert_eq!(diff.unchanged_files, 1);
+        assert_eq!(diff.field_changes.len(), 1);
+        assert_eq!(diff.field_changes[0].field, "edition_number");
+    }
+
+    #[test]
+    fn identical_files_have_no_differences() {
+        let path = write_metadata("same", &metadata(vec![artwork_file("./a.png", "aa", 10)]));
+        let diff = diff_metadata_files(&path, &path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_text(), "No differences\n");
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use crate::file_analyzer::{media_kind, mime_type, MediaKind};
+use crate::metadata_generator::Metadata;
+
+/// Token metadata following the ERC-721 metadata JSON schema with OpenSea extensions
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Erc721Metadata {
+    pub name: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animation_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_url: Option<String>,
+    pub attributes: Vec<Erc721Attribute>,
+    pub properties: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Erc721Attribute {
+    pub trait_type: String,
+    pub value: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Erc721Options {
+    /// Base URI for asset links, e.g. `ipfs://bafy.../` or `https://cdn.example.com/editions/1/`
+    pub uri_base: Option<String>,
+    /// Link to the artwork's page on the gallery or artist website
+    pub external_url: Option<String>,
+}
+
+fn attribute(trait_type: &str, value: Value) -> Erc721Attribute {
+    Erc721Attribute {
+        trait_type: trait_type.to_string(),
+        value,
+        display_type: None,
+        max_value: None,
+    }
+}
+
+/// Converts a metadata document into ERC-721 token metadata
+pub fn to_erc721(metadata: &Metadata, options: &Erc721Options) -> Erc721Metadata {
+    let uri_base = options.uri_base.as_deref();
+
+    // The first image is the token image; the first time-based or interactive file is the animation
+    let image = metadata
+        .artwork_files
+        .iter()
+        .find(|file| media_kind(&file.format) == MediaKind::Image)
+        .map(|file| metadata.asset_uri(file, uri_base));
+    let animation_url = metadata
+        .artwork_files
+        .iter()
+        .find(|file| {
+            matches!(
+                media_kind(&file.format),
+                MediaKind::Video | MediaKind::Audio | MediaKind::Model | MediaKind::Html
+            )
+        })
+        .map(|file| metadata.asset_uri(file, uri_base));
+
+    let mut attributes = vec![
+        Erc721Attribute {
+            trait_type: "Edition".to_string(),
+            value: json!(metadata.edition_number),
+            display_type: Some("number".to_string()),
+            max_value: Some(metadata.total_editions),
+        },
+        attribute("Creator", json!(metadata.artwork_creator)),
+        attribute("Year", json!(metadata.year_of_creation)),
+    ];
+    if !metadata.gallery.is_empty() {
+        attributes.push(attribute("Gallery", json!(metadata.gallery)));
+    }
+    for medium in &metadata.medium {
+        attributes.push(attribute("Medium", json!(medium)));
+    }
+    for keyword in &metadata.keywords {
+        attributes.push(attribute("Keyword", json!(keyword)));
+    }
+
+    let files: Vec<Value> = metadata
+        .artwork_files
+        .iter()
+        .map(|file| {
+            let mut entry = json!({
+                "uri": metadata.asset_uri(file, uri_base),
+                "type": mime_type(&file.format),
+                "name": file.file_name,
+                "size": file.file_size,
+                "blake3": file.file_hash,
+            });
+            if let Some(ipfs_cid) = &file.ipfs_cid {
+                entry["cid"] = json!(ipfs_cid);
+            }
+            entry
+        })
+        .collect();
+
+    let mut properties = json!({
+        "artwork_id": metadata.artwork_id,
+        "edition_number": metadata.edition_number,
+        "total_editions": metadata.total_editions,
+        "issue_date": metadata.issue_date,
+        "files": files,
+    });
+    if let Some(merkle_root) = &metadata.merkle_root {
+        properties["merkle_root"] = json!(merkle_root);
+    }
+    if let Some(certificate_hash) = &metadata.certificate_hash {
+        properties["certificate_hash"] = json!(certificate_hash);
+    }
+
+    let description = if metadata.long_description.is_empty() {
+        metadata.short_description.clone()
+    } else {
+        metadata.long_description.clone()
+    };
+
+    Erc721Metadata {
+        name: format!("{} #{}/{}", metadata.artwork_title, metadata.edition_number, metadata.total_editions),
+        description,
+        image,
+        animation_url,
+        external_url: options.external_url.clone(),
+        attributes,
+        properties,
+    }
+}
@@ -0,0 +1,140 @@
+use std::{fmt, io, path::{Path, PathBuf}};
+use crate::cancel;
+
+/// Errors returned by `MetadataGenerator`, `UnifiedHasher` and the export and verification modules,
+/// carrying the file they concern where known
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file failed
+    Io { path: Option<PathBuf>, source: io::Error },
+    /// A metadata file or checksum manifest could not be parsed
+    Parse { path: Option<PathBuf>, message: String },
+    /// The input was readable but does not describe a valid edition, e.g. an archive without metadata
+    Validation { path: Option<PathBuf>, message: String },
+    /// A fingerprint that vouches for a metadata file does not match it, e.g. a certificate QR code
+    Signature { path: Option<PathBuf>, message: String },
+    /// The local catalogue database could not be opened, read or written
+    Database { path: Option<PathBuf>, message: String },
+    /// The pass was stopped through its `CancellationToken`; nothing was written
+    Cancelled,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Wraps an I/O error for `path`; a cancelled read becomes `Error::Cancelled`
+    pub fn io(path: impl AsRef<Path>, source: io::Error) -> Self {
+        if cancel::is_cancelled(&source) {
+            return Error::Cancelled;
+        }
+        Error::Io { path: Some(path.as_ref().to_path_buf()), source }
+    }
+
+    pub fn parse(path: impl AsRef<Path>, message: impl fmt::Display) -> Self {
+        Error::Parse { path: Some(path.as_ref().to_path_buf()), message: message.to_string() }
+    }
+
+    pub fn validation(path: impl AsRef<Path>, message: impl fmt::Display) -> Self {
+        Error::Validation { path: Some(path.as_ref().to_path_buf()), message: message.to_string() }
+    }
+
+    pub fn signature(path: impl AsRef<Path>, message: impl fmt::Display) -> Self {
+        Error::Signature { path: Some(path.as_ref().to_path_buf()), message: message.to_string() }
+    }
+
+    pub fn database(path: impl AsRef<Path>, message: impl fmt::Display) -> Self {
+        Error::Database { path: Some(path.as_ref().to_path_buf()), message: message.to_string() }
+    }
+
+    /// The file the error concerns, if known
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::Io { path, .. }
+            | Error::Parse { path, .. }
+            | Error::Validation { path, .. }
+            | Error::Signature { path, .. }
+            | Error::Database { path, .. } => path.as_deref(),
+            Error::Cancelled => None,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Error::Cancelled)
+    }
+
+    /// The message without the path, for reports that already name the file
+    pub fn message(&self) -> String {
+        match self {
+            Error::Io { source, .. } => source.to_string(),
+            Error::Parse { message, .. }
+            | Error::Validation { message, .. }
+            | Error::Signature { message, .. }
+            | Error::Database { message, .. } => message.clone(),
+            Error::Cancelled => "Cancelled".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path() {
+            Some(path) => write!(f, "{}: {}", path.display(), self.message()),
+            None => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Self {
+        if cancel::is_cancelled(&source) {
+            return Error::Cancelled;
+        }
+        Error::Io { path: None, source }
+    }
+}
+
+/// Lets code that still returns `std::io::Result` use `?` on library calls; cancellation survives the
+/// round trip so `cancel::is_cancelled` keeps working
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Io { path: None, source } => source,
+            Error::Io { ref source, .. } => io::Error::new(source.kind(), error.to_string()),
+            Error::Parse { .. } | Error::Validation { .. } | Error::Signature { .. } => {
+                io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+            }
+            Error::Database { .. } => io::Error::other(error.to_string()),
+            Error::Cancelled => cancel::cancelled_error(),
+        }
+    }
+}
+
+/// JSON that could not be read or written in memory, where there is no file to name
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        match error.classify() {
+            serde_json::error::Category::Io => Error::from(io::Error::from(error)),
+            _ => Error::Parse { path: None, message: error.to_string() },
+        }
+    }
+}
+
+/// Attaches the file path to an I/O result
+pub trait IoResultExt<T> {
+    fn with_path(self, path: impl AsRef<Path>) -> Result<T>;
+}
+
+impl<T> IoResultExt<T> for io::Result<T> {
+    fn with_path(self, path: impl AsRef<Path>) -> Result<T> {
+        self.map_err(|source| Error::io(path, source))
+    }
+}
@@ -0,0 +1,189 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+/// How a file fared once it was hashed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileOutcome {
+    /// Hashed while generating metadata, with nothing to compare against
+    Hashed,
+    Valid,
+    Invalid,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateOutcome {
+    Hashed,
+    Valid,
+    Invalid,
+    Missing,
+    Failed,
+}
+
+/// What watch mode saw happen to a file it is guarding
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChange {
+    /// The content no longer matches the recorded hash
+    Modified,
+    /// The content matches the recorded hash again
+    Restored,
+    Removed,
+    /// The file changed but could not be read
+    Failed,
+}
+
+/// Typed progress of a generation or verification pass
+/// `index` is zero-based; `total_files` is 0 while streaming an archive, whose entry count is not
+/// known up front, and `BytesHashed` then counts bytes read from the archive itself
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    ScanStarted { total_files: usize },
+    /// A resumed verification found this many files already read by an interrupted run
+    ResumedFromCheckpoint { completed_files: usize, total_files: usize },
+    FileStarted { file_name: String, index: usize, total_files: usize },
+    BytesHashed { file_name: String, bytes_hashed: u64, file_size: u64, index: usize, total_files: usize },
+    FileCompleted { file_name: String, index: usize, total_files: usize, outcome: FileOutcome },
+    FileFailed { file_name: String, index: usize, total_files: usize, error: String },
+    /// Reading a file failed and will be tried again; `attempt` counts retries from 1
+    FileRetrying { file_name: String, index: usize, total_files: usize, attempt: u32, error: String },
+    CertificateChecked { certificate_path: String, outcome: CertificateOutcome, error: Option<String> },
+    Finished { processed_files: usize, failed_files: usize },
+    /// Watch mode has checked the folder and now waits for changes
+    WatchStarted { folder: String, total_files: usize },
+    /// Watch mode re-hashed a file that changed on disk
+    FileChanged { file_name: String, change: FileChange, error: Option<String> },
+}
+
+/// Receives progress events; implemented by the CLI, the GUI, or anything else that wants to follow along
+/// Events arrive on the thread doing the work, so implementations should return quickly
+pub trait ProgressObserver: Send {
+    fn on_event(&self, event: &ProgressEvent);
+}
+
+impl<F: Fn(&ProgressEvent) + Send> ProgressObserver for F {
+    fn on_event(&self, event: &ProgressEvent) {
+        self(event)
+    }
+}
+
+impl ProgressEvent {
+    /// Name of the file the event is about, if any
+    pub fn file_name(&self) -> Option<&str> {
+        match self {
+            ProgressEvent::FileStarted { file_name, .. }
+            | ProgressEvent::BytesHashed { file_name, .. }
+            | ProgressEvent::FileCompleted { file_name, .. }
+            | ProgressEvent::FileFailed { file_name, .. }
+            | ProgressEvent::FileRetrying { file_name, .. }
+            | ProgressEvent::FileChanged { file_name, .. } => Some(file_name),
+            ProgressEvent::CertificateChecked { certificate_path, .. } => Some(certificate_path),
+            ProgressEvent::ScanStarted { .. }
+            | ProgressEvent::ResumedFromCheckpoint { .. }
+            | ProgressEvent::Finished { .. }
+            | ProgressEvent::WatchStarted { .. } => None,
+        }
+    }
+
+    /// Whether the event warns that a guarded file was touched
+    pub fn is_alert(&self) -> bool {
+        matches!(self, ProgressEvent::FileChanged { change, .. } if *change != FileChange::Restored)
+    }
+
+    /// Fraction of the current file that has been hashed
+    pub fn file_progress(&self) -> f32 {
+        match self {
+            ProgressEvent::ScanStarted { .. }
+            | ProgressEvent::ResumedFromCheckpoint { .. }
+            | ProgressEvent::FileStarted { .. }
+            | ProgressEvent::FileRetrying { .. } => 0.0,
+            ProgressEvent::BytesHashed { bytes_hashed, file_size, .. } if *file_size > 0 => {
+                *bytes_hashed as f32 / *file_size as f32
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Fraction of the whole pass that is done
+    pub fn overall_progress(&self) -> f32 {
+        match self {
+            ProgressEvent::ScanStarted { .. } => 0.0,
+            ProgressEvent::ResumedFromCheckpoint { completed_files, total_files } if *total_files > 0 => {
+                *completed_files as f32 / *total_files as f32
+            }
+            ProgressEvent::FileStarted { index, total_files, .. } | ProgressEvent::FileRetrying { index, total_files, .. }
+                if *total_files > 0 => *index as f32 / *total_files as f32,
+            ProgressEvent::BytesHashed { index, total_files, .. } if *total_files > 0 => {
+                (*index as f32 + self.file_progress()) / *total_files as f32
+            }
+            ProgressEvent::BytesHashed { .. } => self.file_progress(),
+            ProgressEvent::FileCompleted { index, total_files, .. } | ProgressEvent::FileFailed { index, total_files, .. }
+                if *total_files > 0 => (*index + 1) as f32 / *total_files as f32,
+            ProgressEvent::CertificateChecked { .. }
+            | ProgressEvent::Finished { .. }
+            | ProgressEvent::WatchStarted { .. }
+            | ProgressEvent::FileChanged { .. } => 1.0,
+            _ => 0.0,
+        }
+    }
+}
+
+/// One-line description suitable for a terminal or log file
+impl fmt::Display for ProgressEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgressEvent::ScanStarted { total_files } if *total_files > 0 => write!(f, "Found {} files", total_files),
+            ProgressEvent::ScanStarted { .. } => write!(f, "Reading archive"),
+            ProgressEvent::ResumedFromCheckpoint { completed_files, total_files } => {
+                write!(f, "Resuming: {} of {} files were already read", completed_files, total_files)
+            }
+            ProgressEvent::FileStarted { file_name, index, total_files } if *total_files > 0 => {
+                write!(f, "Hashing file: {} ({}/{})", file_name, index + 1, total_files)
+            }
+            ProgressEvent::FileStarted { file_name, .. } => write!(f, "Hashing archive entry: {}", file_name),
+            ProgressEvent::BytesHashed { file_name, bytes_hashed, file_size, .. } => {
+                write!(f, "Hashed {} of {} bytes: {}", bytes_hashed, file_size, file_name)
+            }
+            ProgressEvent::FileCompleted { file_name, index, total_files, outcome } => match outcome {
+                FileOutcome::Hashed => write!(f, "Completed: {} ({}/{})", file_name, index + 1, total_files),
+                FileOutcome::Valid => write!(f, "Verified: {} - VALID", file_name),
+                FileOutcome::Invalid => write!(f, "Verified: {} - INVALID", file_name),
+            },
+            ProgressEvent::FileFailed { file_name, error, .. } => write!(f, "Error: {} - {}", file_name, error),
+            ProgressEvent::FileRetrying { file_name, attempt, error, .. } => {
+                write!(f, "Retrying: {} (attempt {}) - {}", file_name, attempt, error)
+            }
+            ProgressEvent::CertificateChecked { certificate_path, outcome, error } => match outcome {
+                CertificateOutcome::Hashed => write!(f, "Certificate hashed: {}", certificate_path),
+                CertificateOutcome::Valid => write!(f, "Certificate verified: {} - VALID", certificate_path),
+                CertificateOutcome::Invalid => write!(f, "Certificate verified: {} - INVALID", certificate_path),
+                CertificateOutcome::Missing => write!(f, "Certificate not found: {}", certificate_path),
+                CertificateOutcome::Failed => write!(
+                    f,
+                    "Warning: Could not hash certificate {}: {}",
+                    certificate_path,
+                    error.as_deref().unwrap_or("unknown error")
+                ),
+            },
+            ProgressEvent::Finished { processed_files, failed_files } => {
+                write!(f, "Finished: {} files processed, {} failed", processed_files, failed_files)
+            }
+            ProgressEvent::WatchStarted { folder, total_files } => {
+                write!(f, "Watching {} files in {} for changes", total_files, folder)
+            }
+            ProgressEvent::FileChanged { file_name, change, error } => match change {
+                FileChange::Modified => write!(f, "Alert: {} was modified and no longer matches its recorded hash", file_name),
+                FileChange::Restored => write!(f, "Restored: {} matches its recorded hash again", file_name),
+                FileChange::Removed => write!(f, "Alert: {} was removed", file_name),
+                FileChange::Failed => write!(
+                    f,
+                    "Alert: {} changed but could not be read: {}",
+                    file_name,
+                    error.as_deref().unwrap_or("unknown error")
+                ),
+            },
+        }
+    }
+}
@@ -5,6 +5,11 @@ pub struct FileMetadata {
     pub format: String,
 }
 
+/// Reads the pixel dimensions of an image file without decoding the whole image
+pub fn image_dimensions(file_path: &Path) -> Option<(u32, u32)> {
+    image::image_dimensions(file_path).ok()
+}
+
 pub fn analyze_file(file_path: &Path) -> Result<FileMetadata> {
     let format = file_path
         .extension()
@@ -15,4 +20,60 @@ pub fn analyze_file(file_path: &Path) -> Result<FileMetadata> {
     Ok(FileMetadata {
         format,
     })
-} 
\ No newline at end of file
+} 
+/// Broad category of an artwork file, used when mapping to external metadata standards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaKind {
+    Image,
+    Video,
+    Audio,
+    Model,
+    Html,
+    Document,
+    Other,
+}
+
+/// Returns the MIME type for a format as recorded in `ArtworkFile.format` (e.g. "PNG")
+pub fn mime_type(format: &str) -> &'static str {
+    match format.to_uppercase().as_str() {
+        "PNG" => "image/png",
+        "JPG" | "JPEG" => "image/jpeg",
+        "GIF" => "image/gif",
+        "WEBP" => "image/webp",
+        "SVG" => "image/svg+xml",
+        "TIF" | "TIFF" => "image/tiff",
+        "BMP" => "image/bmp",
+        "HEIC" => "image/heic",
+        "MP4" | "M4V" => "video/mp4",
+        "MOV" => "video/quicktime",
+        "WEBM" => "video/webm",
+        "MKV" => "video/x-matroska",
+        "AVI" => "video/x-msvideo",
+        "MP3" => "audio/mpeg",
+        "WAV" => "audio/wav",
+        "FLAC" => "audio/flac",
+        "AAC" | "M4A" => "audio/aac",
+        "OGG" => "audio/ogg",
+        "AIFF" | "AIF" => "audio/aiff",
+        "GLB" => "model/gltf-binary",
+        "GLTF" => "model/gltf+json",
+        "HTML" | "HTM" => "text/html",
+        "PDF" => "application/pdf",
+        "TXT" => "text/plain",
+        "JSON" => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+pub fn media_kind(format: &str) -> MediaKind {
+    let mime = mime_type(format);
+    match mime {
+        "text/html" => MediaKind::Html,
+        "application/octet-stream" => MediaKind::Other,
+        _ if mime.starts_with("image/") => MediaKind::Image,
+        _ if mime.starts_with("video/") => MediaKind::Video,
+        _ if mime.starts_with("audio/") => MediaKind::Audio,
+        _ if mime.starts_with("model/") => MediaKind::Model,
+        _ => MediaKind::Document,
+    }
+}
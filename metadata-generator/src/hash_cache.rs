@@ -0,0 +1,154 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+use serde::{Deserialize, Serialize};
+use crate::constants::{user_cache_dir, HASH_CACHE_FILE};
+use crate::error::{Error, IoResultExt, Result};
+
+const CACHE_VERSION: u32 = 1;
+
+/// File identity recorded alongside a cached hash; any difference means the file is hashed again
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct FileStamp {
+    file_size: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+    inode: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CacheEntry {
+    #[serde(flatten)]
+    stamp: FileStamp,
+    file_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct CacheFile {
+    version: u32,
+    entries: HashMap<String, CacheEntry>,
+}
+
+/// On-disk cache of BLAKE3 hashes keyed by path, size, mtime and inode,
+/// so regenerating metadata only re-hashes files that changed
+#[derive(Debug)]
+pub struct HashCache {
+    cache_path: PathBuf,
+    entries: HashMap<String, CacheEntry>,
+    dirty: bool,
+    rehash: bool,
+}
+
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &fs::Metadata) -> u64 {
+    0
+}
+
+pub(crate) fn file_stamp(path: &Path) -> std::io::Result<FileStamp> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Ok(FileStamp {
+        file_size: metadata.len(),
+        modified_secs: modified.as_secs(),
+        modified_nanos: modified.subsec_nanos(),
+        inode: inode(&metadata),
+    })
+}
+
+/// Cache entries are keyed by absolute path so a per-user cache can serve many folders
+fn cache_key(path: &Path) -> Option<String> {
+    fs::canonicalize(path).ok().map(|path| path.to_string_lossy().to_string())
+}
+
+impl HashCache {
+    /// Cache file stored inside the artwork folder (skipped when scanning for artwork files)
+    pub fn folder_cache_path(folder_path: &Path) -> PathBuf {
+        folder_path.join(HASH_CACHE_FILE)
+    }
+
+    /// Cache file shared by every folder, in the user's cache directory
+    pub fn user_cache_path() -> Option<PathBuf> {
+        user_cache_dir().map(|cache_dir| cache_dir.join("hash_cache.json"))
+    }
+
+    /// Loads a cache, starting empty if the file is missing, unreadable or from another version
+    pub fn load(cache_path: &Path) -> Self {
+        let entries = fs::read_to_string(cache_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<CacheFile>(&content).ok())
+            .filter(|cache_file| cache_file.version == CACHE_VERSION)
+            .map(|cache_file| cache_file.entries)
+            .unwrap_or_default();
+        Self {
+            cache_path: cache_path.to_path_buf(),
+            entries,
+            dirty: false,
+            rehash: false,
+        }
+    }
+
+    /// Ignores cached hashes for this run so every file is hashed again; fresh hashes are still
+    /// inserted and saved, and entries for other files (e.g. other folders in a per-user cache) are kept
+    pub fn with_rehash(mut self, rehash: bool) -> Self {
+        self.rehash = rehash;
+        self
+    }
+
+    /// Where the cache is written
+    pub fn path(&self) -> &Path {
+        &self.cache_path
+    }
+
+    /// Returns the cached hash if the file's size, mtime and inode are unchanged
+    pub fn lookup(&self, path: &Path) -> Option<String> {
+        if self.rehash {
+            return None;
+        }
+        let entry = self.entries.get(&cache_key(path)?)?;
+        let stamp = file_stamp(path).ok()?;
+        (entry.stamp == stamp).then(|| entry.file_hash.clone())
+    }
+
+    pub fn insert(&mut self, path: &Path, file_hash: String) {
+        if let (Some(key), Ok(stamp)) = (cache_key(path), file_stamp(path)) {
+            self.entries.insert(key, CacheEntry { stamp, file_hash });
+            self.dirty = true;
+        }
+    }
+
+    /// Writes the cache if it changed, dropping entries for files that no longer exist
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.entries.retain(|path, _| Path::new(path).exists());
+        if let Some(parent) = self.cache_path.parent() {
+            fs::create_dir_all(parent).with_path(parent)?;
+        }
+
+        // Write to a temporary file first so an interrupted save never leaves a truncated cache
+        let cache_file = CacheFile {
+            version: CACHE_VERSION,
+            entries: std::mem::take(&mut self.entries),
+        };
+        let temporary_path = self.cache_path.with_extension("json.tmp");
+        let result = serde_json::to_vec(&cache_file)
+            .map_err(|e| Error::io(&self.cache_path, e.into()))
+            .and_then(|content| fs::write(&temporary_path, content).with_path(&temporary_path))
+            .and_then(|_| fs::rename(&temporary_path, &self.cache_path).with_path(&self.cache_path));
+        self.entries = cache_file.entries;
+        result?;
+
+        self.dirty = false;
+        Ok(())
+    }
+}
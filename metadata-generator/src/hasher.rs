@@ -1,243 +1,266 @@
 use blake3::Hasher;
-use std::{fs::File, sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Arc}, thread};
+use std::{fs::File, io::{self, Read}, path::Path};
 use memmap::Mmap;
-use indicatif::{ProgressBar, ProgressStyle};
+use sha2::{Digest, Sha256};
+use crate::cancel::CancellationToken;
+use crate::error::{Error, IoResultExt, Result};
+use crate::hash_cache::HashCache;
 
-#[allow(dead_code)]
-pub enum ProgressReporter {
-    Cli(ProgressBar),
-    Gui(Arc<AtomicU64>, Arc<AtomicU64>), // file_progress, file_total_size
-    None,
+/// How often `hash_file_with_progress` reports progress
+pub(crate) const PROGRESS_STEP: usize = 1024 * 1024;
+
+/// Buffer used by streaming reads unless configured otherwise
+pub const DEFAULT_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// How file contents are read for hashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadMode {
+    /// Stream files on network shares and removable-media filesystems, memory-map the rest
+    #[default]
+    Auto,
+    /// Memory-map every file; fastest on local disks
+    Mmap,
+    /// Read every file through a buffer, so a share or drive that goes away mid-read gives an
+    /// I/O error instead of SIGBUS
+    Streaming,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    pub mode: ReadMode,
+    /// Buffer size for streaming reads, in bytes
+    pub buffer_size: usize,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            mode: ReadMode::Auto,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+        }
+    }
 }
 
 pub struct UnifiedHasher {
-    progress_reporter: ProgressReporter,
-    current_file: Arc<std::sync::Mutex<String>>,
-    is_complete: Arc<AtomicBool>,
-    hash_result: Arc<std::sync::Mutex<Option<Result<String, std::io::Error>>>>,
+    read_options: ReadOptions,
+    cache: Option<std::sync::Mutex<HashCache>>,
+    cancellation: CancellationToken,
 }
 
 impl UnifiedHasher {
-    #[allow(dead_code)]
     pub fn new_cli() -> Self {
         Self {
-            progress_reporter: ProgressReporter::None,
-            current_file: Arc::new(std::sync::Mutex::new(String::new())),
-            is_complete: Arc::new(AtomicBool::new(false)),
-            hash_result: Arc::new(std::sync::Mutex::new(None)),
+            read_options: ReadOptions::default(),
+            cache: None,
+            cancellation: CancellationToken::new(),
         }
     }
 
+    /// Same as `new_cli`; the GUI follows progress through `hash_file_with_progress`
     pub fn new_gui() -> Self {
-        Self {
-            progress_reporter: ProgressReporter::Gui(
-                Arc::new(AtomicU64::new(0)),
-                Arc::new(AtomicU64::new(0))
-            ),
-            current_file: Arc::new(std::sync::Mutex::new(String::new())),
-            is_complete: Arc::new(AtomicBool::new(false)),
-            hash_result: Arc::new(std::sync::Mutex::new(None)),
-        }
+        Self::new_cli()
     }
 
-    #[allow(dead_code)]
-    pub fn hash_file(&self, path: &str) -> std::io::Result<String> {
-        let file = File::open(path)?;
-        let file_size = file.metadata()?.len();
+    pub fn with_read_options(mut self, read_options: ReadOptions) -> Self {
+        self.read_options = read_options;
+        self
+    }
 
-        // Update current file name
-        if let Ok(mut current_file) = self.current_file.lock() {
-            *current_file = std::path::Path::new(path)
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-        }
+    /// Consult an on-disk hash cache in `hash_file_cached`; `hash_file` always reads the file
+    pub fn with_cache(mut self, cache: HashCache) -> Self {
+        self.cache = Some(std::sync::Mutex::new(cache));
+        self
+    }
 
-        // Setup progress reporting based on type
-        match &self.progress_reporter {
-            ProgressReporter::Cli(_) => {
-                // CLI progress bar setup
-                let pb = ProgressBar::new(file_size);
-                pb.set_style(ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
-                    .progress_chars("#>-"));
-                
-                let mmap = unsafe { Mmap::map(&file)? };
-                let mut hasher = Hasher::new();
-                let chunk_size = 8192;
-
-                for chunk in mmap.chunks(chunk_size) {
-                    hasher.update(chunk);
-                    pb.inc(chunk.len() as u64);
-                }
-
-                pb.finish_with_message("Hashing complete");
-                let hash = hasher.finalize();
-                Ok(hash.to_hex().to_string())
-            }
-            ProgressReporter::Gui(file_progress, file_total_size) => {
-                // GUI progress tracking
-                file_total_size.store(file_size, Ordering::Relaxed);
-                file_progress.store(0, Ordering::Relaxed);
-
-                let mmap = unsafe { Mmap::map(&file)? };
-                let mut hasher = Hasher::new();
-                let chunk_size = 8192;
-
-                for chunk in mmap.chunks(chunk_size) {
-                    hasher.update(chunk);
-                    file_progress.fetch_add(chunk.len() as u64, Ordering::Relaxed);
-                }
-
-                let hash = hasher.finalize();
-                Ok(hash.to_hex().to_string())
-            }
-            ProgressReporter::None => {
-                // No progress reporting (fallback)
-                let mmap = unsafe { Mmap::map(&file)? };
-                let mut hasher = Hasher::new();
-                let chunk_size = 8192;
-
-                for chunk in mmap.chunks(chunk_size) {
-                    hasher.update(chunk);
-                }
-
-                let hash = hasher.finalize();
-                Ok(hash.to_hex().to_string())
-            }
-        }
+    /// Stop hashing with a cancelled error as soon as the token is cancelled
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
     }
 
-    pub fn hash_file_async(&self, path: &str) {
-        let path = path.to_string();
-        let current_file_clone = Arc::clone(&self.current_file);
-        let is_complete_clone = Arc::clone(&self.is_complete);
-        let hash_result_clone = Arc::clone(&self.hash_result);
+    /// Returns the cached hash of an unchanged file, if a cache is configured
+    pub fn cached_hash(&self, path: &str) -> Option<String> {
+        self.cache.as_ref()?.lock().ok()?.lookup(Path::new(path))
+    }
 
-        // Clone progress reporter for async use
-        let progress_reporter = match &self.progress_reporter {
-            ProgressReporter::Gui(file_progress, file_total_size) => {
-                ProgressReporter::Gui(
-                    Arc::clone(file_progress),
-                    Arc::clone(file_total_size)
-                )
-            }
-            _ => ProgressReporter::None,
-        };
+    pub fn record_hash(&self, path: &str, hash: &str) {
+        if let Some(Ok(mut cache)) = self.cache.as_ref().map(|cache| cache.lock()) {
+            cache.insert(Path::new(path), hash.to_string());
+        }
+    }
 
-        // Reset state for new file
-        if let ProgressReporter::Gui(file_progress, _) = &self.progress_reporter {
-            file_progress.store(0, Ordering::Relaxed);
+    /// Hashes a file unless the cache holds a hash for it with the same size, mtime and inode
+    pub fn hash_file_cached(&self, path: &str, on_progress: &mut dyn FnMut(u64, u64)) -> Result<String> {
+        if let Some(hash) = self.cached_hash(path) {
+            return Ok(hash);
         }
-        self.is_complete.store(false, Ordering::Relaxed);
-        if let Ok(mut result) = self.hash_result.lock() {
-            *result = None;
+        let hash = self.hash_file_with_progress(path, on_progress)?;
+        self.record_hash(path, &hash);
+        Ok(hash)
+    }
+
+    pub fn save_cache(&self) -> Result<()> {
+        match self.cache.as_ref().map(|cache| cache.lock()) {
+            Some(Ok(mut cache)) => cache.save(),
+            _ => Ok(()),
         }
+    }
 
-        thread::spawn(move || {
-            // Update current file name
-            if let Ok(mut current_file) = current_file_clone.lock() {
-                *current_file = std::path::Path::new(&path)
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
-            }
+    pub fn hash_file(&self, path: &str) -> Result<String> {
+        self.hash_file_with_progress(path, &mut |_, _| {})
+    }
 
-            // Open file and get size
-            let file = match File::open(&path) {
-                Ok(f) => f,
-                Err(e) => {
-                    if let Ok(mut result) = hash_result_clone.lock() {
-                        *result = Some(Err(e));
-                    }
-                    is_complete_clone.store(true, Ordering::Relaxed);
-                    return;
-                }
-            };
+    /// Hashes a file in 1 MiB steps, calling `on_progress(bytes_hashed, file_size)` after each step
+    /// Runs on the caller's thread so progress is pushed as it happens rather than polled, and checks for
+    /// cancellation between steps
+    pub fn hash_file_with_progress(&self, path: &str, on_progress: &mut dyn FnMut(u64, u64)) -> Result<String> {
+        let file = File::open(path).with_path(path)?;
+        let file_size = file.metadata().with_path(path)?.len();
 
-            let file_size = match file.metadata() {
-                Ok(m) => m.len(),
-                Err(e) => {
-                    if let Ok(mut result) = hash_result_clone.lock() {
-                        *result = Some(Err(e));
-                    }
-                    is_complete_clone.store(true, Ordering::Relaxed);
-                    return;
-                }
-            };
+        // Mapping an empty file fails on some platforms, and there is nothing to gain from it
+        let streaming = match self.read_options.mode {
+            ReadMode::Auto => file_size == 0 || prefers_streaming(Path::new(path)),
+            ReadMode::Mmap => file_size == 0,
+            ReadMode::Streaming => true,
+        };
+        if streaming {
+            self.hash_stream(file, file_size, on_progress).with_path(path)
+        } else {
+            self.hash_mapped(&file, file_size, on_progress).with_path(path)
+        }
+    }
 
-            // Setup progress tracking
-            match &progress_reporter {
-                ProgressReporter::Gui(_file_progress, file_total_size) => {
-                    file_total_size.store(file_size, Ordering::Relaxed);
-                }
-                _ => {}
-            }
+    /// Computes a RustCrypto digest of a file as lowercase hex, streamed through a buffer of `buffer_size` bytes
+    pub fn digest_file<D: Digest>(&self, path: &Path) -> Result<String> {
+        self.digest_file_with_progress::<D>(path, &mut |_, _| {})
+    }
 
-            // Memory map the file
-            let mmap = match unsafe { Mmap::map(&file) } {
-                Ok(m) => m,
-                Err(e) => {
-                    if let Ok(mut result) = hash_result_clone.lock() {
-                        *result = Some(Err(e));
-                    }
-                    is_complete_clone.store(true, Ordering::Relaxed);
-                    return;
-                }
+    /// `digest_file` calling `on_progress(bytes_hashed, file_size)` about every 1 MiB, like `hash_file_with_progress`
+    pub fn digest_file_with_progress<D: Digest>(&self, path: &Path, on_progress: &mut dyn FnMut(u64, u64)) -> Result<String> {
+        let mut file = File::open(path).with_path(path)?;
+        let file_size = file.metadata().with_path(path)?.len();
+        let mut hasher = D::new();
+        let mut buffer = vec![0u8; self.read_options.buffer_size.max(4096)];
+        let mut bytes_hashed = 0;
+        let mut bytes_reported = 0;
+        loop {
+            self.cancellation.check().with_path(path)?;
+            let read = match file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(Error::io(path, e)),
             };
-
-            // Hash the file
-            let mut hasher = Hasher::new();
-            let chunk_size = 8192;
-
-            for chunk in mmap.chunks(chunk_size) {
-                hasher.update(chunk);
-                
-                // Update progress
-                match &progress_reporter {
-                    ProgressReporter::Gui(file_progress, _) => {
-                        file_progress.fetch_add(chunk.len() as u64, Ordering::Relaxed);
-                    }
-                    _ => {}
-                }
+            hasher.update(&buffer[..read]);
+            bytes_hashed += read as u64;
+            if bytes_hashed - bytes_reported >= PROGRESS_STEP as u64 {
+                on_progress(bytes_hashed, file_size);
+                bytes_reported = bytes_hashed;
             }
+        }
+        if bytes_hashed > bytes_reported {
+            on_progress(bytes_hashed, file_size);
+        }
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
 
-            let hash = hasher.finalize();
-            let hash_hex = hash.to_hex().to_string();
+    fn hash_mapped(&self, file: &File, file_size: u64, on_progress: &mut dyn FnMut(u64, u64)) -> io::Result<String> {
+        let mmap = unsafe { Mmap::map(file)? };
+        let mut hasher = Hasher::new();
+        let mut bytes_hashed = 0;
 
-            // Store result and mark as complete
-            if let Ok(mut result) = hash_result_clone.lock() {
-                *result = Some(Ok(hash_hex));
-            }
-            is_complete_clone.store(true, Ordering::Relaxed);
-        });
-    }
-
-    pub fn get_file_progress(&self) -> f32 {
-        match &self.progress_reporter {
-            ProgressReporter::Gui(file_progress, file_total_size) => {
-                let file_total = file_total_size.load(Ordering::Relaxed);
-                if file_total == 0 {
-                    0.0
-                } else {
-                    file_progress.load(Ordering::Relaxed) as f32 / file_total as f32
-                }
-            }
-            _ => 0.0,
+        for chunk in mmap.chunks(PROGRESS_STEP) {
+            self.cancellation.check()?;
+            hasher.update(chunk);
+            bytes_hashed += chunk.len() as u64;
+            on_progress(bytes_hashed, file_size);
         }
+
+        let hash = hasher.finalize();
+        Ok(hash.to_hex().to_string())
     }
 
-    pub fn get_result(&self) -> Option<Result<String, std::io::Error>> {
-        if let Ok(result) = self.hash_result.lock() {
-            match &*result {
-                Some(Ok(hash)) => Some(Ok(hash.clone())),
-                Some(Err(e)) => Some(Err(std::io::Error::new(e.kind(), e.to_string()))),
-                None => None,
+    /// Reads through a buffer of `buffer_size` bytes; a failed read is returned rather than retried
+    fn hash_stream(&self, mut file: File, file_size: u64, on_progress: &mut dyn FnMut(u64, u64)) -> io::Result<String> {
+        let mut buffer = vec![0u8; self.read_options.buffer_size.max(4096)];
+        let mut hasher = Hasher::new();
+        let mut bytes_hashed = 0;
+        let mut bytes_reported = 0;
+
+        loop {
+            self.cancellation.check()?;
+            let read = match file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            hasher.update(&buffer[..read]);
+            bytes_hashed += read as u64;
+            if bytes_hashed - bytes_reported >= PROGRESS_STEP as u64 {
+                on_progress(bytes_hashed, file_size);
+                bytes_reported = bytes_hashed;
             }
-        } else {
-            None
         }
+        if bytes_hashed > bytes_reported {
+            on_progress(bytes_hashed, file_size);
+        }
+
+        let hash = hasher.finalize();
+        Ok(hash.to_hex().to_string())
     }
-} 
\ No newline at end of file
+}
+
+/// Whether a file lives on a network share or a filesystem typical of removable drives, where
+/// memory-mapped reads turn a disconnect into SIGBUS
+#[cfg(target_os = "linux")]
+fn prefers_streaming(path: &Path) -> bool {
+    // statfs magic numbers: NFS, SMB, CIFS, SMB2, FUSE (sshfs, ntfs-3g, exFAT), FAT, exFAT, NTFS, 9P, Ceph, AFS
+    const STREAMING_FILESYSTEMS: [u32; 11] = [
+        0x6969, 0x517B, 0xFF53_4D42, 0xFE53_4D42, 0x6573_5546, 0x4D44, 0x2011_BAB0, 0x5346_544E, 0x0102_1997,
+        0x00C3_6400, 0x5346_414F,
+    ];
+    statfs(path).is_some_and(|stat| STREAMING_FILESYSTEMS.contains(&(stat.f_type as u32)))
+}
+
+/// Whether a file lives on a network share or a filesystem typical of removable drives, where
+/// memory-mapped reads turn a disconnect into SIGBUS
+#[cfg(target_os = "macos")]
+fn prefers_streaming(path: &Path) -> bool {
+    let Some(stat) = statfs(path) else {
+        return false;
+    };
+    let filesystem: String = stat
+        .f_fstypename
+        .iter()
+        .take_while(|&&character| character != 0)
+        .map(|&character| character as u8 as char)
+        .collect();
+    stat.f_flags & libc::MNT_LOCAL as u32 == 0 || matches!(filesystem.as_str(), "msdos" | "exfat" | "ntfs")
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn prefers_streaming(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn statfs(path: &Path) -> Option<libc::statfs> {
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statfs>::uninit();
+    // The struct is only read once statfs has filled it in
+    if unsafe { libc::statfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    Some(unsafe { stat.assume_init() })
+}
+
+/// Computes the SHA-256 of a file as lowercase hex, for standards that require it instead of BLAKE3
+pub fn sha256_file(path: &Path) -> Result<String> {
+    UnifiedHasher::new_cli().digest_file::<Sha256>(path)
+}
@@ -0,0 +1,369 @@
+use std::{fmt, fs::File, io::Read, path::Path};
+use sha2::{Digest, Sha256};
+use crate::cancel::CancellationToken;
+use crate::error::{IoResultExt, Result};
+
+/// Chunk size used by `ipfs add` (kubo's default `size-262144` chunker)
+pub const CHUNK_SIZE: usize = 262_144;
+
+/// Maximum number of links per node in kubo's balanced DAG layout
+pub const MAX_LINKS: usize = 174;
+
+const CODEC_RAW: u64 = 0x55;
+const CODEC_DAG_PB: u64 = 0x70;
+const MULTIHASH_SHA2_256: u64 = 0x12;
+
+/// UnixFS node types from the UnixFS protobuf schema
+const UNIXFS_DIRECTORY: u64 = 1;
+const UNIXFS_FILE: u64 = 2;
+
+/// A CIDv1 using a SHA2-256 multihash
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cid {
+    codec: u64,
+    digest: [u8; 32],
+}
+
+impl Cid {
+    fn new(codec: u64, block: &[u8]) -> Self {
+        Self {
+            codec,
+            digest: Sha256::digest(block).into(),
+        }
+    }
+
+    /// All-zero CID used to reserve space before the real root is known
+    pub fn placeholder() -> Self {
+        Self {
+            codec: CODEC_DAG_PB,
+            digest: [0; 32],
+        }
+    }
+
+    /// Binary CID as embedded in dag-pb links and CAR files
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(36);
+        write_varint(&mut bytes, 1);
+        write_varint(&mut bytes, self.codec);
+        write_varint(&mut bytes, MULTIHASH_SHA2_256);
+        write_varint(&mut bytes, self.digest.len() as u64);
+        bytes.extend_from_slice(&self.digest);
+        bytes
+    }
+}
+
+impl fmt::Display for Cid {
+    /// Formats the CID as multibase base32, e.g. `bafkrei...` or `bafybei...`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "b{}", base32_lower(&self.to_bytes()))
+    }
+}
+
+/// RFC 4648 base32, lowercase and without padding, as used by multibase `b`
+fn base32_lower(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut output = String::with_capacity(bytes.len() * 8 / 5 + 1);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        output.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    output
+}
+
+pub(crate) fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn write_varint_field(output: &mut Vec<u8>, field: u64, value: u64) {
+    write_varint(output, field << 3);
+    write_varint(output, value);
+}
+
+fn write_bytes_field(output: &mut Vec<u8>, field: u64, value: &[u8]) {
+    write_varint(output, (field << 3) | 2);
+    write_varint(output, value.len() as u64);
+    output.extend_from_slice(value);
+}
+
+/// A link to a child block, carrying the sizes needed by the parent node
+#[derive(Debug, Clone)]
+pub struct DagLink {
+    pub cid: Cid,
+    pub name: String,
+    /// Total serialized size of the child and everything below it
+    pub tsize: u64,
+    /// Number of file content bytes below the child
+    pub file_size: u64,
+}
+
+/// Encodes a dag-pb node; links are serialized before data as required by the dag-pb spec
+fn encode_dag_pb(links: &[DagLink], data: &[u8]) -> Vec<u8> {
+    let mut node = Vec::new();
+    for link in links {
+        let mut encoded_link = Vec::new();
+        write_bytes_field(&mut encoded_link, 1, &link.cid.to_bytes());
+        write_bytes_field(&mut encoded_link, 2, link.name.as_bytes());
+        write_varint_field(&mut encoded_link, 3, link.tsize);
+        write_bytes_field(&mut node, 2, &encoded_link);
+    }
+    write_bytes_field(&mut node, 1, data);
+    node
+}
+
+/// Receives every block produced while building a DAG, e.g. for writing a CAR file
+pub type BlockSink<'a> = Box<dyn FnMut(&Cid, &[u8]) -> Result<()> + 'a>;
+
+/// Builds a UnixFS file with kubo's `--cid-version=1` defaults:
+/// 256 KiB chunks stored as raw leaves in a balanced DAG of at most 174 links per node
+pub struct UnixFsFileBuilder<'a> {
+    buffer: Vec<u8>,
+    levels: Vec<Vec<DagLink>>,
+    leaf_count: usize,
+    block_sink: Option<BlockSink<'a>>,
+}
+
+impl Default for UnixFsFileBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> UnixFsFileBuilder<'a> {
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+            levels: Vec::new(),
+            leaf_count: 0,
+            block_sink: None,
+        }
+    }
+
+    pub fn with_block_sink(mut self, block_sink: BlockSink<'a>) -> Self {
+        self.block_sink = Some(block_sink);
+        self
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) -> Result<()> {
+        while !data.is_empty() {
+            let take = (CHUNK_SIZE - self.buffer.len()).min(data.len());
+            self.buffer.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.buffer.len() == CHUNK_SIZE {
+                self.flush_leaf()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn emit_block(&mut self, cid: &Cid, block: &[u8]) -> Result<()> {
+        match self.block_sink.as_mut() {
+            Some(block_sink) => block_sink(cid, block),
+            None => Ok(()),
+        }
+    }
+
+    fn flush_leaf(&mut self) -> Result<()> {
+        let chunk = std::mem::take(&mut self.buffer);
+        let cid = Cid::new(CODEC_RAW, &chunk);
+        self.emit_block(&cid, &chunk)?;
+        self.leaf_count += 1;
+        self.push_link(0, DagLink {
+            cid,
+            name: String::new(),
+            tsize: chunk.len() as u64,
+            file_size: chunk.len() as u64,
+        })?;
+        self.buffer = Vec::with_capacity(CHUNK_SIZE);
+        Ok(())
+    }
+
+    fn push_link(&mut self, level: usize, link: DagLink) -> Result<()> {
+        if self.levels.len() <= level {
+            self.levels.push(Vec::new());
+        }
+        self.levels[level].push(link);
+        if self.levels[level].len() == MAX_LINKS {
+            let links = std::mem::take(&mut self.levels[level]);
+            let parent = self.build_file_node(links)?;
+            self.push_link(level + 1, parent)?;
+        }
+        Ok(())
+    }
+
+    fn build_file_node(&mut self, links: Vec<DagLink>) -> Result<DagLink> {
+        let file_size: u64 = links.iter().map(|link| link.file_size).sum();
+
+        let mut unixfs = Vec::new();
+        write_varint_field(&mut unixfs, 1, UNIXFS_FILE);
+        write_varint_field(&mut unixfs, 3, file_size);
+        for link in &links {
+            write_varint_field(&mut unixfs, 4, link.file_size);
+        }
+
+        let block = encode_dag_pb(&links, &unixfs);
+        let cid = Cid::new(CODEC_DAG_PB, &block);
+        self.emit_block(&cid, &block)?;
+
+        Ok(DagLink {
+            cid,
+            name: String::new(),
+            tsize: block.len() as u64 + links.iter().map(|link| link.tsize).sum::<u64>(),
+            file_size,
+        })
+    }
+
+    /// Flushes the remaining data and returns a link to the root of the file DAG
+    pub fn finalize(mut self) -> Result<DagLink> {
+        // An empty file is still a single (empty) raw leaf
+        if !self.buffer.is_empty() || self.leaf_count == 0 {
+            self.flush_leaf()?;
+        }
+
+        let mut level = 0;
+        loop {
+            let higher_levels_empty = self.levels[level + 1..].iter().all(|links| links.is_empty());
+            let links = std::mem::take(&mut self.levels[level]);
+            if links.len() == 1 && higher_levels_empty {
+                // A single-chunk file is just its raw leaf
+                return Ok(links.into_iter().next().unwrap());
+            }
+            if !links.is_empty() {
+                let parent = self.build_file_node(links)?;
+                if self.levels.len() <= level + 1 {
+                    self.levels.push(Vec::new());
+                }
+                self.levels[level + 1].push(parent);
+            }
+            level += 1;
+        }
+    }
+}
+
+/// Builds a UnixFS directory node from named links, sorted by name as kubo does
+pub fn build_directory_node(
+    mut links: Vec<DagLink>,
+    block_sink: &mut dyn FnMut(&Cid, &[u8]) -> Result<()>,
+) -> Result<DagLink> {
+    links.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut unixfs = Vec::new();
+    write_varint_field(&mut unixfs, 1, UNIXFS_DIRECTORY);
+
+    let block = encode_dag_pb(&links, &unixfs);
+    let cid = Cid::new(CODEC_DAG_PB, &block);
+    block_sink(&cid, &block)?;
+
+    Ok(DagLink {
+        cid,
+        name: String::new(),
+        tsize: block.len() as u64 + links.iter().map(|link| link.tsize).sum::<u64>(),
+        file_size: links.iter().map(|link| link.file_size).sum(),
+    })
+}
+
+/// Computes the CIDv1 `ipfs add --cid-version=1` would assign to a file, without any network access
+/// Stops with `Error::Cancelled` between chunks once the token is cancelled
+pub fn file_cid(path: &Path, cancellation: &CancellationToken) -> Result<String> {
+    let mut file = File::open(path).with_path(path)?;
+    let mut builder = UnixFsFileBuilder::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+
+    loop {
+        cancellation.check().with_path(path)?;
+        let read = file.read(&mut buffer).with_path(path)?;
+        if read == 0 {
+            break;
+        }
+        builder.update(&buffer[..read])?;
+    }
+
+    Ok(builder.finalize()?.cid.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn write_temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ipfs-test-{}-{name}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn cid_of(name: &str, contents: &[u8]) -> String {
+        let path = write_temp_file(name, contents);
+        let cid = file_cid(&path, &CancellationToken::new()).unwrap();
+        std::fs::remove_file(path).unwrap();
+        cid
+    }
+
+    #[test]
+    fn empty_file_is_an_empty_raw_leaf() {
+        assert_eq!(cid_of("empty", b""), "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku");
+    }
+
+    #[test]
+    fn single_chunk_file_is_a_raw_leaf() {
+        assert_eq!(
+            cid_of("one-chunk", &vec![0u8; CHUNK_SIZE]),
+            "bafkreiekhhjkxu4ztk3tyng3er3ijhg56mb44oe3gwbgquhzu4afrg2ksa"
+        );
+    }
+
+    #[test]
+    fn files_over_max_links_chunks_get_a_second_level() {
+        let contents = vec![0u8; (MAX_LINKS + 1) * CHUNK_SIZE + 1];
+        let mut blocks = Vec::new();
+        let mut builder = UnixFsFileBuilder::new().with_block_sink(Box::new(|cid: &Cid, block: &[u8]| {
+            blocks.push((cid.clone(), block.to_vec()));
+            Ok(())
+        }));
+        builder.update(&contents).unwrap();
+        let root = builder.finalize().unwrap();
+
+        // 176 raw leaves, one full 174-link node, one node for the last two leaves and the root
+        assert_eq!(blocks.len(), MAX_LINKS + 2 + 3);
+        let (root_cid, root_block) = blocks.last().unwrap();
+        assert_eq!(root_cid, &root.cid);
+        assert_eq!(root.file_size, contents.len() as u64);
+
+        let mut unixfs = Vec::new();
+        write_varint_field(&mut unixfs, 1, UNIXFS_FILE);
+        write_varint_field(&mut unixfs, 3, contents.len() as u64);
+        write_varint_field(&mut unixfs, 4, (MAX_LINKS * CHUNK_SIZE) as u64);
+        write_varint_field(&mut unixfs, 4, (CHUNK_SIZE + 1) as u64);
+        assert!(root_block.ends_with(&unixfs));
+
+        let path = write_temp_file("many-chunks", &contents);
+        let cid = file_cid(&path, &CancellationToken::new()).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(cid, root.cid.to_string());
+        assert_eq!(cid, "bafybeicyowx3udu4hzfyo2ekfduhsfhirbv5j2uhcuyz4zh53vrbx2jdfa");
+    }
+
+    #[test]
+    fn cancelled_token_stops_the_read() {
+        let path = write_temp_file("cancelled", b"data");
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let error = file_cid(&path, &cancellation).unwrap_err();
+        std::fs::remove_file(path).unwrap();
+        assert!(error.is_cancelled());
+    }
+}
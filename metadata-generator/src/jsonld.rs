@@ -0,0 +1,121 @@
+use std::path::PathBuf;
+use serde_json::{json, Value};
+use crate::error::Result;
+use crate::file_analyzer::mime_type;
+use crate::hasher::sha256_file;
+use crate::metadata_generator::Metadata;
+
+#[derive(Debug, Clone, Default)]
+pub struct JsonLdOptions {
+    /// Base URI for asset links, e.g. `https://cdn.example.com/editions/1/`
+    pub uri_base: Option<String>,
+    /// Folder containing the artwork files, used to compute the SHA-256 schema.org expects
+    pub base_folder: Option<PathBuf>,
+    /// Canonical URL of the page the record describes, used as the node identifier
+    pub page_url: Option<String>,
+}
+
+/// Maps a metadata document to a schema.org `VisualArtwork` JSON-LD record
+pub fn to_json_ld(metadata: &Metadata, options: &JsonLdOptions) -> Result<Value> {
+    let uri_base = options.uri_base.as_deref();
+
+    let mut associated_media = Vec::new();
+    for file in &metadata.artwork_files {
+        let mut media = json!({
+            "@type": "MediaObject",
+            "name": file.file_name,
+            "contentUrl": metadata.asset_uri(file, uri_base),
+            "encodingFormat": mime_type(&file.format),
+            "contentSize": format!("{} B", file.file_size),
+            "identifier": {
+                "@type": "PropertyValue",
+                "propertyID": "BLAKE3",
+                "value": file.file_hash,
+            },
+        });
+        if let Some(base_folder) = &options.base_folder {
+            let file_path = base_folder.join(&file.file_name);
+            if file_path.exists() {
+                media["sha256"] = json!(sha256_file(&file_path)?);
+            }
+        }
+        associated_media.push(media);
+    }
+
+    let mut additional_properties = vec![json!({
+        "@type": "PropertyValue",
+        "name": "Edition number",
+        "value": metadata.edition_number,
+        "maxValue": metadata.total_editions,
+    })];
+    if let Some(merkle_root) = &metadata.merkle_root {
+        additional_properties.push(json!({
+            "@type": "PropertyValue",
+            "name": "Edition Merkle root",
+            "value": merkle_root,
+        }));
+    }
+
+    let mut record = json!({
+        "@context": "https://schema.org",
+        "@type": "VisualArtwork",
+        "identifier": metadata.artwork_id,
+        "name": metadata.artwork_title,
+        "creator": {
+            "@type": "Person",
+            "name": metadata.artwork_creator,
+        },
+        "dateCreated": metadata.year_of_creation.to_string(),
+        // schema.org defines artEdition as the total number of copies in the edition
+        "artEdition": metadata.total_editions,
+        "keywords": metadata.keywords,
+        "artMedium": metadata.medium.join(", "),
+        "associatedMedia": associated_media,
+        "additionalProperty": additional_properties,
+    });
+
+    if !metadata.artwork_short_title.is_empty() {
+        record["alternateName"] = json!(metadata.artwork_short_title);
+    }
+    if !metadata.long_description.is_empty() {
+        record["description"] = json!(metadata.long_description);
+    }
+    if !metadata.short_description.is_empty() {
+        record["abstract"] = json!(metadata.short_description);
+    }
+    if !metadata.issue_date.is_empty() {
+        record["datePublished"] = json!(metadata.issue_date);
+    }
+    if !metadata.gallery.is_empty() {
+        record["provider"] = json!({
+            "@type": "Organization",
+            "name": metadata.gallery,
+        });
+    }
+    if let Some(page_url) = &options.page_url {
+        record["@id"] = json!(page_url);
+        record["url"] = json!(page_url);
+    }
+
+    Ok(record)
+}
+
+/// Wraps a JSON-LD record in a `<script>` element for embedding in an HTML page
+pub fn to_html_script(record: &Value) -> Result<String> {
+    // Escape "</" so text fields cannot close the script element early
+    let json = serde_json::to_string_pretty(record)?.replace("</", "<\\/");
+    Ok(format!("<script type=\"application/ld+json\">\n{}\n</script>\n", json))
+}
+
+/// Serializes the record as JSON-LD, or as an embeddable script element for `.html` outputs
+pub fn render(record: &Value, output_path: &std::path::Path) -> Result<String> {
+    let is_html = output_path
+        .extension()
+        .map(|ext| matches!(ext.to_string_lossy().to_lowercase().as_str(), "html" | "htm"))
+        .unwrap_or(false);
+    if is_html {
+        to_html_script(record)
+    } else {
+        Ok(serde_json::to_string_pretty(record)?)
+    }
+}
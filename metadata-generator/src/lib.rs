@@ -0,0 +1,42 @@
+//! Metadata generation and verification for digital artwork editions
+//!
+//! `MetadataGenerator` hashes an artwork folder into a `Metadata` record and verifies folders,
+//! checksum manifests and packaged archives against one, producing a `VerificationReport`.
+//! Both the `cli` and `MetadataGenerator` binaries are built on this crate.
+
+pub mod constants;
+pub mod hasher;
+pub mod metadata_generator;
+pub mod file_analyzer;
+pub mod certificate_qr;
+pub mod merkle;
+pub mod ipfs;
+pub mod car;
+pub mod erc721;
+pub mod tzip21;
+pub mod jsonld;
+pub mod xml;
+pub mod xmp;
+pub mod bagit;
+pub mod checksums;
+pub mod archive;
+pub mod hash_cache;
+pub mod diff;
+pub mod report;
+pub mod events;
+pub mod cancel;
+pub mod error;
+pub mod checkpoint;
+pub mod audit;
+pub mod watch;
+pub mod catalogue;
+mod time;
+
+pub use cancel::CancellationToken;
+pub use error::{Error, Result};
+pub use events::{ProgressEvent, ProgressObserver};
+pub use hasher::UnifiedHasher;
+pub use metadata_generator::{
+    detect_certificate_of_authenticity, read_metadata_file, ArtworkFile, Metadata, MetadataGenerator, UpdateSummary,
+    VerificationReport, VerificationResult,
+};
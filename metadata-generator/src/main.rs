@@ -1,18 +1,17 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use std::{fs::File, io::Error};
 
-
-pub mod constants;
-pub mod hasher;
-pub mod metadata_generator;
-pub mod file_analyzer;
-
+use metadata_generator::{
+    archive, audit, bagit, cancel, car, catalogue, certificate_qr, checksums, constants, diff, erc721, events, hash_cache, hasher,
+    jsonld, merkle, report, tzip21, watch, xmp,
+};
 use metadata_generator::{MetadataGenerator, Metadata};
 
 #[derive(Parser)]
+#[command(subcommand_negates_reqs = true)]
 struct Cli {
-    #[arg(short = 'p', long = "path")]
-    path: std::path::PathBuf,
+    #[arg(short = 'p', long = "path", required = true)]
+    path: Option<std::path::PathBuf>,
 
     #[arg(short = 'm', long = "metadata")]
     metadata: Option<std::path::PathBuf>,
@@ -22,70 +21,811 @@ struct Cli {
 
     #[arg(short = 'f', long = "metadata-file")]
     metadata_file: Option<std::path::PathBuf>,
+
+    /// Keep a checkpoint next to the metadata file and skip files an interrupted verification already read
+    #[arg(long = "resume")]
+    resume: bool,
+
+    /// Record the IPFS CIDv1 of each artwork file (computed offline)
+    #[arg(long = "ipfs-cid")]
+    ipfs_cid: bool,
+
+    /// Reuse hashes of files whose size, mtime and inode are unchanged since the last run
+    #[arg(long = "hash-cache", value_enum)]
+    hash_cache: Option<HashCacheLocation>,
+
+    /// Ignore cached hashes and hash every file again (the cache is refreshed)
+    #[arg(long = "rehash")]
+    rehash: bool,
+
+    /// How files are read for hashing; auto streams from network shares and removable drives
+    #[arg(long = "read-mode", value_enum, global = true, default_value = "auto")]
+    read_mode: ReadMode,
+
+    /// Buffer size in KiB for streaming reads
+    #[arg(long = "buffer-kib", global = true, default_value_t = 1024)]
+    buffer_kib: usize,
+
+    /// Times to retry a file after a transient read error before it is reported as failed
+    #[arg(long = "retries", global = true, default_value_t = constants::DEFAULT_READ_RETRIES)]
+    retries: u32,
+
+    /// Do not record generated or verified metadata files in the local catalogue
+    #[arg(long = "no-catalogue", global = true)]
+    no_catalogue: bool,
+
+    /// Report format for verification results; without it a text summary is printed, or the
+    /// format follows the --report-output extension
+    #[arg(long = "report-format", global = true)]
+    report_format: Option<report::ReportFormat>,
+
+    /// Write the verification report to a file (the format follows its extension unless given)
+    #[arg(long = "report-output", global = true)]
+    report_output: Option<std::path::PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+impl Cli {
+    fn read_options(&self) -> hasher::ReadOptions {
+        hasher::ReadOptions {
+            mode: match self.read_mode {
+                ReadMode::Auto => hasher::ReadMode::Auto,
+                ReadMode::Mmap => hasher::ReadMode::Mmap,
+                ReadMode::Stream => hasher::ReadMode::Streaming,
+            },
+            buffer_size: self.buffer_kib.max(1) * 1024,
+        }
+    }
+}
+
+/// Prints progress events one per line; byte-level progress is left to the GUI
+struct CliObserver {
+    to_stderr: bool,
+}
+
+impl events::ProgressObserver for CliObserver {
+    fn on_event(&self, event: &events::ProgressEvent) {
+        if matches!(event, events::ProgressEvent::BytesHashed { .. }) {
+            return;
+        }
+        if self.to_stderr {
+            eprintln!("{}", event);
+        } else {
+            println!("{}", event);
+        }
+    }
+}
+
+/// Prints watch progress like `CliObserver` and copies alerts to the alert log
+struct WatchObserver {
+    alert_log: Option<watch::AlertLog>,
+}
+
+impl events::ProgressObserver for WatchObserver {
+    fn on_event(&self, event: &events::ProgressEvent) {
+        if matches!(event, events::ProgressEvent::BytesHashed { .. }) {
+            return;
+        }
+        if event.is_alert() {
+            eprintln!("⚠️ {}", event);
+        } else {
+            println!("{}", event);
+        }
+        if let Some(alert_log) = &self.alert_log {
+            if let Err(e) = alert_log.record(event) {
+                eprintln!("Warning: Could not write the alert log: {}", e);
+            }
+        }
+    }
+}
+
+/// Cancels the running pass on the first Ctrl-C; a second Ctrl-C quits straight away
+fn cancel_on_ctrl_c() -> cancel::CancellationToken {
+    let cancellation = cancel::CancellationToken::new();
+    let handler_cancellation = cancellation.clone();
+    let result = ctrlc::set_handler(move || {
+        if handler_cancellation.is_cancelled() {
+            std::process::exit(130);
+        }
+        eprintln!("Cancelling... press Ctrl-C again to quit immediately");
+        handler_cancellation.cancel();
+    });
+    if let Err(e) = result {
+        eprintln!("Warning: Ctrl-C will not cancel cleanly: {}", e);
+    }
+    cancellation
+}
+
+/// How the verification commands report their results
+struct ReportOptions {
+    /// None prints the usual text summary
+    format: Option<report::ReportFormat>,
+    output: Option<std::path::PathBuf>,
+}
+
+impl ReportOptions {
+    fn from_args(args: &Cli) -> Result<Self, Error> {
+        let format = args
+            .report_format
+            .or_else(|| args.report_output.as_deref().and_then(report::ReportFormat::from_path));
+        if format.is_none() && args.report_output.is_some() {
+            return Err(Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Use --report-format json, junit or html with --report-output",
+            ));
+        }
+        Ok(Self {
+            format,
+            output: args.report_output.clone(),
+        })
+    }
+
+    /// A machine-readable report printed to stdout keeps progress messages on stderr
+    fn to_stdout(&self) -> bool {
+        self.format.is_some() && self.output.is_none()
+    }
+
+    fn log(&self, message: &str) {
+        if self.to_stdout() {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    fn observer(&self) -> CliObserver {
+        CliObserver { to_stderr: self.to_stdout() }
+    }
+
+    /// Prints or writes the report; `subject` names what was verified
+    fn emit(&self, report: &metadata_generator::VerificationReport, subject: &std::path::Path) -> Result<(), Error> {
+        let Some(format) = self.format else {
+            print_verification_report(report);
+            return Ok(());
+        };
+        let subject = subject.display().to_string();
+        match &self.output {
+            Some(output) => {
+                report::write_report(report, format, &subject, output)?;
+                println!("{} report saved to: {}", format.label(), output.display());
+                println!("{}", if report.overall_valid { "✅ Verification passed" } else { "❌ Verification failed" });
+            }
+            None => print!("{}", report::render_report(report, format, &subject)?),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum HashCacheLocation {
+    /// Next to the artwork files
+    Folder,
+    /// In the user's cache directory, shared by every folder
+    User,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReadMode {
+    /// Stream from network shares and removable drives, memory-map local files
+    Auto,
+    /// Always memory-map; fastest on local disks
+    Mmap,
+    /// Always read through a buffer; a drive that disconnects mid-read fails only that file
+    Stream,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write a QR code (.png or .svg) encoding the artwork ID, edition and metadata hash
+    Qr {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        #[arg(short = 'o', long = "output")]
+        output: std::path::PathBuf,
+    },
+    /// Write a printable SVG certificate with the QR code embedded
+    Certificate {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        #[arg(short = 'o', long = "output")]
+        output: std::path::PathBuf,
+    },
+    /// Check the text decoded from a certificate QR code against a metadata file
+    VerifyQr {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        #[arg(long = "payload")]
+        payload: String,
+    },
+    /// Write a Merkle inclusion proof showing a single file belongs to the edition
+    MerkleProof {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        /// Path or file name of the artwork file as recorded in the metadata
+        #[arg(long = "file")]
+        file: String,
+
+        #[arg(short = 'o', long = "output")]
+        output: std::path::PathBuf,
+    },
+    /// Export the edition in another format
+    Export {
+        #[command(subcommand)]
+        format: ExportFormat,
+    },
+    /// Refresh an existing metadata file: hash new and changed files, drop deleted ones, keep descriptive fields
+    Update {
+        /// Folder containing the artwork files (defaults to the metadata file's folder)
+        #[arg(short = 'p', long = "path")]
+        path: Option<std::path::PathBuf>,
+
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+    },
+    /// Show what changed between two metadata files, e.g. two drafts or two editions
+    Diff {
+        #[arg(long = "old")]
+        old: std::path::PathBuf,
+
+        #[arg(long = "new")]
+        new: std::path::PathBuf,
+
+        /// Print the changes as JSON instead of text
+        #[arg(long = "json")]
+        json: bool,
+
+        /// Write the changes to a file instead of printing them
+        #[arg(short = 'o', long = "output")]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Package the metadata JSON, artwork files and certificate into a .zip or .tar.zst archive
+    Package {
+        #[arg(short = 'p', long = "path")]
+        path: std::path::PathBuf,
+
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        /// Archive to write; the format follows the extension (.zip or .tar.zst)
+        #[arg(short = 'o', long = "output")]
+        output: std::path::PathBuf,
+    },
+    /// Check a packaged edition against its embedded metadata without extracting it
+    VerifyArchive {
+        #[arg(long = "archive")]
+        archive: std::path::PathBuf,
+    },
+    /// Validate a BagIt bag, including the edition metadata in its payload
+    VerifyBag {
+        #[arg(long = "bag")]
+        bag: std::path::PathBuf,
+    },
+    /// Check a Merkle inclusion proof, optionally against the actual file on disk
+    VerifyProof {
+        #[arg(long = "proof")]
+        proof: std::path::PathBuf,
+
+        #[arg(long = "file")]
+        file: Option<std::path::PathBuf>,
+    },
+    /// Check a folder once, then keep watching it and alert as soon as a recorded file changes
+    Watch {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        /// Folder containing the artwork files (defaults to the metadata file's folder)
+        #[arg(short = 'p', long = "path")]
+        path: Option<std::path::PathBuf>,
+
+        /// Also append every alert, with a timestamp, to this file
+        #[arg(long = "alert-log")]
+        alert_log: Option<std::path::PathBuf>,
+    },
+    /// Search and maintain the local catalogue of generated and verified editions
+    Catalogue {
+        /// Catalogue database (defaults to the user data directory)
+        #[arg(long = "database")]
+        database: Option<std::path::PathBuf>,
+
+        #[command(subcommand)]
+        action: CatalogueCommand,
+    },
+    /// Periodic fixity checks of registered editions, recorded in a hash-chained audit log
+    Audit {
+        /// Registry of editions to audit (defaults to the user data directory)
+        #[arg(long = "registry")]
+        registry: Option<std::path::PathBuf>,
+
+        /// Audit log to append to (defaults to the user data directory)
+        #[arg(long = "log")]
+        log: Option<std::path::PathBuf>,
+
+        #[command(subcommand)]
+        action: AuditCommand,
+    },
 }
 
 fn verify_metadata() -> Result<(), Error> {
     let args = Cli::parse();
     
+    let report_options = ReportOptions::from_args(&args)?;
+    let read_options = args.read_options();
     let metadata_file = args.metadata_file.ok_or_else(|| {
         Error::new(std::io::ErrorKind::InvalidInput, "Metadata file path is required for verification")
     })?;
     
-    println!("Verifying metadata file: {}", metadata_file.display());
-    println!("Base folder: {}", args.path.display());
+    let base_folder = args.path.expect("--path is required");
+
+    report_options.log(&format!("Verifying metadata file: {}", metadata_file.display()));
+    report_options.log(&format!("Base folder: {}", base_folder.display()));
     
     let generator = MetadataGenerator::new_cli()
-        .with_progress_callback(metadata_generator::ProgressCallback::Cli(Box::new(|message| {
-            println!("{}", message);
-        })));
+        .with_observer(report_options.observer())
+        .with_cancellation(cancel_on_ctrl_c())
+        .with_read_options(read_options)
+        .with_read_retries(args.retries)
+        .with_checkpoint(args.resume);
+
+    // A plain checksum list (SHA256SUMS, .md5, ...) can stand in for our metadata JSON
+    let result = if checksums::is_checksum_manifest(&metadata_file) {
+        generator.verify_checksum_manifest(&metadata_file, &base_folder)
+    } else {
+        generator.verify_metadata_file_with_progress(&metadata_file, &base_folder)
+    };
 
-    match generator.verify_metadata_file_with_progress(&metadata_file, &args.path) {
+    match result {
         Ok(report) => {
-            println!("\n=== Verification Complete ===");
-            println!("Metadata file hash: {}", report.metadata_file_hash);
-            println!("Total files: {}", report.total_files);
-            println!("Valid files: {}", report.valid_files);
-            println!("Invalid files: {}", report.invalid_files);
-            
-            // Certificate verification
-            if let Some(certificate_valid) = report.certificate_valid {
-                if certificate_valid {
-                    println!("✅ Certificate is valid!");
-                } else {
-                    println!("❌ Certificate is invalid!");
-                }
-                if let Some(certificate_hash) = &report.certificate_hash {
-                    println!("Certificate hash: {}", certificate_hash);
+            report_options.emit(&report, &metadata_file)?;
+            if !args.no_catalogue && !checksums::is_checksum_manifest(&metadata_file) {
+                if let Err(e) = catalogue::record_in_default_catalogue(&metadata_file, &base_folder, Some(&report)) {
+                    eprintln!("Warning: Could not update the catalogue: {}", e);
                 }
+            }
+        }
+        Err(e) => {
+            if !e.is_cancelled() {
+                eprintln!("❌ Verification failed: {}", e);
+            } else if args.resume {
+                // The checkpoint has just been written, so "nothing was written" would be wrong here
+                eprintln!("⏹️ Cancelled: progress kept in the checkpoint; run again with --resume to continue");
+                std::process::exit(130);
+            }
+            return Err(e.into());
+        }
+    }
+    
+    Ok(())
+}
+
+fn update_metadata(
+    path: Option<&std::path::Path>,
+    metadata_file: &std::path::Path,
+    ipfs_cid: bool,
+    hash_cache: Option<HashCacheLocation>,
+    rehash: bool,
+    read_options: hasher::ReadOptions,
+    record_catalogue: bool,
+) -> Result<(), Error> {
+    let folder_path = path
+        .map(|path| path.to_path_buf())
+        .or_else(|| metadata_file.parent().filter(|parent| !parent.as_os_str().is_empty()).map(|parent| parent.to_path_buf()))
+        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    println!("Updating metadata file: {}", metadata_file.display());
+    println!("Folder: {}", folder_path.display());
+
+    let generator = MetadataGenerator::new_cli()
+        .with_observer(CliObserver { to_stderr: false })
+        .with_cancellation(cancel_on_ctrl_c())
+        .with_read_options(read_options)
+        .with_ipfs_cid(ipfs_cid);
+    let generator = match open_hash_cache(hash_cache, rehash, &folder_path) {
+        Some(cache) => generator.with_hash_cache(cache),
+        None => generator,
+    };
+
+    let summary = generator.update_metadata(&folder_path, metadata_file)?;
+    for file_name in &summary.added {
+        println!("➕ Added: {}", file_name);
+    }
+    for file_name in &summary.changed {
+        println!("✏️ Changed: {}", file_name);
+    }
+    for file_name in &summary.removed {
+        println!("➖ Removed: {}", file_name);
+    }
+    if summary.certificate_changed {
+        println!("✏️ Certificate hash updated");
+    }
+    println!(
+        "Added: {}, changed: {}, removed: {}, unchanged: {}",
+        summary.added.len(),
+        summary.changed.len(),
+        summary.removed.len(),
+        summary.unchanged
+    );
+    if summary.has_changes() {
+        println!("Metadata saved to: {}", summary.metadata_path.display());
+    } else {
+        println!("✅ Metadata is already up to date");
+    }
+    if record_catalogue {
+        if let Err(e) = catalogue::record_in_default_catalogue(&summary.metadata_path, &folder_path, None) {
+            eprintln!("Warning: Could not update the catalogue: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn diff_metadata(old: &std::path::Path, new: &std::path::Path, json: bool, output: Option<&std::path::Path>) -> Result<(), Error> {
+    let diff = diff::diff_metadata_files(old, new)?;
+    let rendered = if json {
+        format!("{}\n", serde_json::to_string_pretty(&diff)?)
+    } else {
+        diff.to_text()
+    };
+
+    match output {
+        Some(output) => {
+            std::fs::write(output, rendered)?;
+            println!("Differences saved to: {}", output.display());
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+fn package(path: &std::path::Path, metadata_file: &std::path::Path, output: &std::path::Path) -> Result<(), Error> {
+    println!("Packaging edition: {}", path.display());
+    let generator = MetadataGenerator::new_cli().with_cancellation(cancel_on_ctrl_c());
+    let summary = archive::package_edition(&generator, path, metadata_file, output)?;
+    println!("Files: {}", summary.file_count);
+    println!("Archive size: {} bytes", summary.archive_size);
+    println!("Archive saved to: {}", summary.archive_path.display());
+    Ok(())
+}
+
+fn verify_archive(archive: &std::path::Path, report_options: &ReportOptions) -> Result<(), Error> {
+    report_options.log(&format!("Verifying archive: {}", archive.display()));
+
+    let generator = MetadataGenerator::new_cli()
+        .with_observer(report_options.observer())
+        .with_cancellation(cancel_on_ctrl_c());
+
+    match generator.verify_archive(archive) {
+        Ok(report) => report_options.emit(&report, archive)?,
+        Err(e) => {
+            if !e.is_cancelled() {
+                eprintln!("❌ Archive verification failed: {}", e);
+            }
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
+
+fn verify_bag(
+    bag: &std::path::Path,
+    report_options: &ReportOptions,
+    read_options: hasher::ReadOptions,
+    retries: u32,
+) -> Result<(), Error> {
+    report_options.log(&format!("Validating bag: {}", bag.display()));
+
+    let generator = MetadataGenerator::new_cli()
+        .with_observer(report_options.observer())
+        .with_cancellation(cancel_on_ctrl_c())
+        .with_read_options(read_options)
+        .with_read_retries(retries);
+
+    match bagit::validate_bag(&generator, bag) {
+        Ok(report) => report_options.emit(&report, bag)?,
+        Err(e) => {
+            if !e.is_cancelled() {
+                eprintln!("❌ Bag validation failed: {}", e);
+            }
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
+
+fn watch_folder(
+    metadata_file: &std::path::Path,
+    path: Option<&std::path::Path>,
+    alert_log: Option<&std::path::Path>,
+    read_options: hasher::ReadOptions,
+    retries: u32,
+) -> Result<(), Error> {
+    let folder_path = path
+        .map(|path| path.to_path_buf())
+        .or_else(|| metadata_file.parent().filter(|parent| !parent.as_os_str().is_empty()).map(|parent| parent.to_path_buf()))
+        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    println!("Watching metadata file: {}", metadata_file.display());
+    println!("Press Ctrl-C to stop");
+
+    let generator = MetadataGenerator::new_cli()
+        .with_observer(WatchObserver { alert_log: alert_log.map(watch::AlertLog::open) })
+        .with_cancellation(cancel_on_ctrl_c())
+        .with_read_options(read_options)
+        .with_read_retries(retries);
+    generator.watch_metadata_file(metadata_file, &folder_path)?;
+    println!("Stopped watching");
+    Ok(())
+}
+
+fn run_catalogue_command(database: Option<&std::path::Path>, action: &CatalogueCommand) -> Result<(), Error> {
+    let catalogue = match database {
+        Some(database) => catalogue::Catalogue::open(database)?,
+        None => catalogue::Catalogue::open_default()?,
+    };
+
+    match action {
+        CatalogueCommand::Search { text, title, creator, keyword, gallery, file_hash, json } => {
+            let query = catalogue::CatalogueQuery {
+                text: text.clone(),
+                title: title.clone(),
+                creator: creator.clone(),
+                keyword: keyword.clone(),
+                gallery: gallery.clone(),
+                file_hash: file_hash.clone(),
+            };
+            let entries = catalogue.search(&query)?;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+                return Ok(());
+            }
+            for entry in &entries {
+                let verification = match (entry.last_verification_valid, &entry.last_verified_at) {
+                    (Some(true), Some(verified_at)) => format!("✅ verified {}", verified_at),
+                    (Some(false), Some(verified_at)) => format!("❌ failed verification {}", verified_at),
+                    _ => "not verified".to_string(),
+                };
+                println!(
+                    "{} - {} ({}), edition {}/{}, {}",
+                    entry.artwork_creator,
+                    entry.artwork_title,
+                    entry.year_of_creation,
+                    entry.edition_number,
+                    entry.total_editions,
+                    entry.gallery
+                );
+                println!("    {} ({} files, {})", entry.metadata_path.display(), entry.file_count, verification);
+            }
+            println!("{} editions found", entries.len());
+        }
+        CatalogueCommand::Scan { path } => {
+            println!("Scanning for metadata files: {}", path.display());
+            let summary = catalogue.scan(path)?;
+            for skipped in &summary.skipped {
+                println!("⚠️ Skipped (not readable metadata): {}", skipped.display());
+            }
+            println!("Recorded {} metadata files in: {}", summary.recorded, catalogue.path().display());
+        }
+        CatalogueCommand::Add { metadata_file, path } => {
+            let base_folder = path
+                .clone()
+                .or_else(|| metadata_file.parent().filter(|parent| !parent.as_os_str().is_empty()).map(|parent| parent.to_path_buf()))
+                .unwrap_or_else(|| std::path::PathBuf::from("."));
+            catalogue.record(metadata_file, &base_folder)?;
+            println!("Recorded in the catalogue: {}", metadata_file.display());
+        }
+        CatalogueCommand::Remove { metadata_file } => {
+            if catalogue.remove(metadata_file)? {
+                println!("Removed from the catalogue: {}", metadata_file.display());
             } else {
-                println!("ℹ️ No certificate found");
+                println!("Not in the catalogue: {}", metadata_file.display());
             }
-            
-            if report.overall_valid {
-                println!("✅ All files and certificate are valid!");
+        }
+    }
+    Ok(())
+}
+
+fn run_audit_command(
+    registry_path: Option<&std::path::Path>,
+    log_path: Option<&std::path::Path>,
+    action: &AuditCommand,
+    read_options: hasher::ReadOptions,
+    retries: u32,
+) -> Result<(), Error> {
+    let no_data_dir = || Error::new(std::io::ErrorKind::NotFound, "No user data directory found; pass --registry and --log");
+    let registry_path = registry_path
+        .map(|path| path.to_path_buf())
+        .or_else(audit::AuditRegistry::default_path)
+        .ok_or_else(no_data_dir)?;
+    let log = audit::AuditLog::open(
+        &log_path.map(|path| path.to_path_buf()).or_else(audit::AuditLog::default_path).ok_or_else(no_data_dir)?,
+    );
+    let mut registry = audit::AuditRegistry::load(&registry_path)?;
+
+    match action {
+        AuditCommand::Add { metadata_file, path } => {
+            let base_folder = path
+                .clone()
+                .or_else(|| metadata_file.parent().filter(|parent| !parent.as_os_str().is_empty()).map(|parent| parent.to_path_buf()))
+                .unwrap_or_else(|| std::path::PathBuf::from("."));
+            if registry.add(metadata_file, &base_folder)? {
+                registry.save()?;
+                println!("Registered for auditing: {}", metadata_file.display());
             } else {
-                println!("❌ Some files or certificate are invalid!");
-                println!("\nDetailed Results:");
-                for result in &report.results {
-                    if result.is_valid {
-                        println!("✅ {}", result.file_name);
-                    } else {
-                        println!("❌ {} - {}", result.file_name, result.error.as_deref().unwrap_or("Unknown error"));
+                println!("Already registered: {}", metadata_file.display());
+            }
+        }
+        AuditCommand::Remove { metadata_file } => {
+            if registry.remove(metadata_file) {
+                registry.save()?;
+                println!("No longer auditing: {}", metadata_file.display());
+            } else {
+                println!("Not registered: {}", metadata_file.display());
+            }
+        }
+        AuditCommand::List => {
+            if registry.targets.is_empty() {
+                println!("No editions registered; add one with `audit add -f <metadata file>`");
+            }
+            for target in &registry.targets {
+                println!("{} (files in {})", target.metadata_file.display(), target.base_folder.display());
+            }
+        }
+        AuditCommand::Run { every_hours } => {
+            if registry.targets.is_empty() {
+                println!("No editions registered; add one with `audit add -f <metadata file>`");
+                return Ok(());
+            }
+            let cancellation = cancel_on_ctrl_c();
+            let generator = MetadataGenerator::new_cli()
+                .with_observer(CliObserver { to_stderr: false })
+                .with_cancellation(cancellation.clone())
+                .with_read_options(read_options)
+                .with_read_retries(retries);
+            loop {
+                if let Some(sequence) = log.verify_chain()? {
+                    eprintln!("⚠️ Audit log chain is broken at record {}; new records are still appended", sequence);
+                }
+                for record in audit::run_audit(&generator, &registry, &log)? {
+                    match (&record.summary, &record.error) {
+                        (Some(summary), _) if summary.overall_valid => {
+                            println!("✅ #{} {} - {} files intact", record.sequence, record.metadata_file.display(), summary.total_files)
+                        }
+                        (Some(summary), _) => println!(
+                            "❌ #{} {} - {} of {} files failed",
+                            record.sequence,
+                            record.metadata_file.display(),
+                            summary.invalid_files,
+                            summary.total_files
+                        ),
+                        (None, error) => println!(
+                            "❌ #{} {} - {}",
+                            record.sequence,
+                            record.metadata_file.display(),
+                            error.as_deref().unwrap_or("Unknown error")
+                        ),
+                    }
+                }
+                println!("Audit log: {}", log.path().display());
+
+                let Some(every_hours) = every_hours else {
+                    break;
+                };
+                println!("Next audit in {} hours", every_hours);
+                let next_run = std::time::Instant::now() + std::time::Duration::from_secs(every_hours * 3600);
+                // Ctrl-C between runs just stops the schedule; every finished run is already logged
+                while std::time::Instant::now() < next_run {
+                    if cancellation.is_cancelled() {
+                        return Ok(());
                     }
+                    std::thread::sleep(std::time::Duration::from_secs(1));
                 }
             }
         }
-        Err(e) => {
-            eprintln!("❌ Verification failed: {}", e);
-            return Err(e);
+        AuditCommand::History { file } => {
+            let history = log.file_history(file)?;
+            if history.is_empty() {
+                println!("No audit records for: {}", file.display());
+            }
+            let mut previous_hash: Option<&str> = None;
+            for event in &history {
+                let status = if event.result.is_valid { "✅" } else { "❌" };
+                // Reads that failed have no hash to compare
+                let read = !event.result.actual_hash.is_empty();
+                let changed = read && previous_hash.is_some_and(|previous_hash| previous_hash != event.result.actual_hash);
+                println!(
+                    "{} #{} {} {}{} ({})",
+                    status,
+                    event.sequence,
+                    event.recorded_at,
+                    event.result.error.as_deref().unwrap_or(&event.result.actual_hash),
+                    if changed { " - changed since the previous check" } else { "" },
+                    event.metadata_file.display()
+                );
+                if read {
+                    previous_hash = Some(&event.result.actual_hash);
+                }
+            }
         }
+        AuditCommand::Check => match log.verify_chain()? {
+            None => {
+                println!("✅ Audit log intact: {} records", log.records()?.len());
+                if let Some(last) = log.last_record()? {
+                    println!("Latest record hash: {} (keep a copy to detect records removed from the end)", last.record_hash);
+                }
+            }
+            Some(sequence) => {
+                eprintln!("❌ Audit log chain is broken at record {}: {}", sequence, log.path().display());
+                return Err(Error::new(std::io::ErrorKind::InvalidData, "Audit log was tampered with"));
+            }
+        },
     }
-    
     Ok(())
 }
 
+fn print_verification_report(report: &metadata_generator::VerificationReport) {
+    println!("\n=== Verification Complete ===");
+    println!("Metadata file hash: {}", report.metadata_file_hash);
+    println!("Total files: {}", report.total_files);
+    println!("Valid files: {}", report.valid_files);
+    println!("Invalid files: {}", report.invalid_files);
+    
+    // Merkle root verification
+    match report.merkle_root_valid {
+        Some(true) => println!("✅ Merkle root matches the files on disk!"),
+        Some(false) => println!("❌ Merkle root does not match the files on disk!"),
+        None => println!("ℹ️ No Merkle root recorded"),
+    }
+
+    // Certificate verification
+    if let Some(certificate_valid) = report.certificate_valid {
+        if certificate_valid {
+            println!("✅ Certificate is valid!");
+        } else {
+            println!("❌ Certificate is invalid!");
+        }
+        if let Some(certificate_hash) = &report.certificate_hash {
+            println!("Certificate hash: {}", certificate_hash);
+        }
+    } else {
+        println!("ℹ️ No certificate found");
+    }
+    
+    if report.overall_valid {
+        println!("✅ All files and certificate are valid!");
+    } else {
+        println!("❌ Some files or certificate are invalid!");
+        println!("\nDetailed Results:");
+        for result in &report.results {
+            if result.is_valid {
+                println!("✅ {}", result.file_name);
+            } else {
+                println!("❌ {} - {}", result.file_name, result.error.as_deref().unwrap_or("Unknown error"));
+            }
+        }
+    }
+}
+
+/// Opens the hash cache selected on the command line; `--rehash` ignores its entries for this run
+fn open_hash_cache(
+    location: Option<HashCacheLocation>,
+    rehash: bool,
+    folder_path: &std::path::Path,
+) -> Option<hash_cache::HashCache> {
+    let cache_path = match location? {
+        HashCacheLocation::Folder => hash_cache::HashCache::folder_cache_path(folder_path),
+        HashCacheLocation::User => hash_cache::HashCache::user_cache_path()?,
+    };
+    Some(hash_cache::HashCache::load(&cache_path).with_rehash(rehash))
+}
+
 fn check_for_metadata_file() -> Result<(), Error> {
     let args = Cli::parse();
+    let read_options = args.read_options();
+    let folder_path = args.path.expect("--path is required");
 
     if let Some(metadata_path) = &args.metadata {
         let metadata_file = File::open(metadata_path)?;
@@ -93,7 +833,7 @@ fn check_for_metadata_file() -> Result<(), Error> {
         println!("Metadata: {:?}", metadata);
 
         // Detect certificate of authenticity
-        let certificate_of_authenticity = metadata_generator::detect_certificate_of_authenticity(&args.path);
+        let certificate_of_authenticity = metadata_generator::detect_certificate_of_authenticity(&folder_path);
         if certificate_of_authenticity.is_none() {
             println!("Warning: No certificate of authenticity PDF found in 'certificate' folder");
         } else {
@@ -103,12 +843,22 @@ fn check_for_metadata_file() -> Result<(), Error> {
 
         // Use the unified metadata generator to process the folder
         let generator = MetadataGenerator::new_cli()
-            .with_progress_callback(metadata_generator::ProgressCallback::Cli(Box::new(|message| {
-                println!("{}", message);
-            })));
+            .with_observer(CliObserver { to_stderr: false })
+            .with_cancellation(cancel_on_ctrl_c())
+            .with_read_options(read_options)
+            .with_ipfs_cid(args.ipfs_cid);
+        let generator = match open_hash_cache(args.hash_cache, args.rehash, &folder_path) {
+            Some(cache) => generator.with_hash_cache(cache),
+            None => generator,
+        };
 
-        let output_path = generator.generate_metadata(&args.path, &metadata)?;
+        let output_path = generator.generate_metadata(&folder_path, &metadata)?;
         println!("Metadata saved to: {}", output_path.display());
+        if !args.no_catalogue {
+            if let Err(e) = catalogue::record_in_default_catalogue(&output_path, &folder_path, None) {
+                eprintln!("Warning: Could not update the catalogue: {}", e);
+            }
+        }
     } else {
         let mut metadata = Metadata {
             artwork_id: "".to_string(),
@@ -127,6 +877,8 @@ fn check_for_metadata_file() -> Result<(), Error> {
             certificate_of_authenticity: None,
             certificate_hash: None,
             artwork_files: Vec::new(),
+            merkle_root: None,
+            ipfs_root_cid: None,
         };
 
         let mut user_input = Vec::new();
@@ -225,7 +977,7 @@ fn check_for_metadata_file() -> Result<(), Error> {
             .collect();
 
         // Detect certificate of authenticity
-        let certificate_of_authenticity = metadata_generator::detect_certificate_of_authenticity(&args.path);
+        let certificate_of_authenticity = metadata_generator::detect_certificate_of_authenticity(&folder_path);
         if certificate_of_authenticity.is_none() {
             println!("Warning: No certificate of authenticity PDF found in 'certificate' folder");
         } else {
@@ -255,23 +1007,467 @@ fn check_for_metadata_file() -> Result<(), Error> {
 
         // Use the unified metadata generator
         let generator = MetadataGenerator::new_cli()
-            .with_progress_callback(metadata_generator::ProgressCallback::Cli(Box::new(|message| {
-                println!("{}", message);
-            })));
+            .with_observer(CliObserver { to_stderr: false })
+            .with_cancellation(cancel_on_ctrl_c())
+            .with_read_options(read_options)
+            .with_ipfs_cid(args.ipfs_cid);
+        let generator = match open_hash_cache(args.hash_cache, args.rehash, &folder_path) {
+            Some(cache) => generator.with_hash_cache(cache),
+            None => generator,
+        };
 
-        let output_path = generator.generate_metadata(&args.path, &metadata)?;
+        let output_path = generator.generate_metadata(&folder_path, &metadata)?;
         println!("Metadata saved to: {}", output_path.display());
+        if !args.no_catalogue {
+            if let Err(e) = catalogue::record_in_default_catalogue(&output_path, &folder_path, None) {
+                eprintln!("Warning: Could not update the catalogue: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Subcommand)]
+enum ExportFormat {
+    /// Write an IPFS CARv1 archive of the artwork folder, certificate folder and metadata JSON
+    Car {
+        #[arg(short = 'p', long = "path")]
+        path: std::path::PathBuf,
+
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        #[arg(short = 'o', long = "output")]
+        output: std::path::PathBuf,
+
+        /// Record the root CID in the metadata file after exporting
+        #[arg(long = "update-metadata")]
+        update_metadata: bool,
+    },
+    /// Write ERC-721 / OpenSea token metadata JSON
+    Erc721 {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        #[arg(short = 'o', long = "output")]
+        output: std::path::PathBuf,
+
+        /// Base URI for asset links (defaults to the recorded IPFS CIDs)
+        #[arg(long = "uri-base")]
+        uri_base: Option<String>,
+
+        /// Link to the artwork's page on the gallery or artist website
+        #[arg(long = "external-url")]
+        external_url: Option<String>,
+    },
+    /// Write Tezos TZIP-21 token metadata JSON
+    Tzip21 {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        #[arg(short = 'o', long = "output")]
+        output: std::path::PathBuf,
+
+        /// Folder containing the artwork files, used to read image dimensions
+        #[arg(short = 'p', long = "path")]
+        path: Option<std::path::PathBuf>,
+
+        /// Base URI for asset links (defaults to the recorded IPFS CIDs)
+        #[arg(long = "uri-base")]
+        uri_base: Option<String>,
+
+        /// Creator Tezos address or name (repeatable, defaults to the artwork creator)
+        #[arg(long = "creator")]
+        creators: Vec<String>,
+
+        #[arg(long = "rights")]
+        rights: Option<String>,
+    },
+    /// Write a schema.org VisualArtwork JSON-LD record (a .html output gets an embeddable script tag)
+    Jsonld {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        #[arg(short = 'o', long = "output")]
+        output: std::path::PathBuf,
+
+        /// Folder containing the artwork files, used to compute SHA-256 hashes
+        #[arg(short = 'p', long = "path")]
+        path: Option<std::path::PathBuf>,
+
+        /// Base URI for asset links (defaults to the recorded IPFS CIDs)
+        #[arg(long = "uri-base")]
+        uri_base: Option<String>,
+
+        /// URL of the web page describing the artwork
+        #[arg(long = "url")]
+        url: Option<String>,
+    },
+    /// Write an XMP sidecar (.xmp) for every artwork file
+    Xmp {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        /// Folder to write the sidecars to (defaults to the folder containing the metadata file)
+        #[arg(short = 'o', long = "output")]
+        output: Option<std::path::PathBuf>,
+
+        #[arg(long = "rights")]
+        rights: Option<String>,
+    },
+    /// Lay out the edition as a BagIt (RFC 8493) bag for archival deposit
+    Bagit {
+        #[arg(short = 'p', long = "path")]
+        path: std::path::PathBuf,
+
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        /// Bag folder to create (must be empty or not exist)
+        #[arg(short = 'o', long = "output")]
+        output: std::path::PathBuf,
+    },
+    /// Write a sha256sum/md5sum-style checksum manifest of the artwork files
+    Checksums {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        /// Folder containing the artwork files (defaults to the folder containing the metadata file)
+        #[arg(short = 'p', long = "path")]
+        path: Option<std::path::PathBuf>,
+
+        /// Output file (defaults to e.g. SHA256SUMS next to the artwork files)
+        #[arg(short = 'o', long = "output")]
+        output: Option<std::path::PathBuf>,
+
+        /// md5, sha1, sha256, sha512 or blake3
+        #[arg(long = "algorithm", default_value = "sha256")]
+        algorithm: String,
+
+        /// Write BSD-style lines (`SHA256 (file) = hash`) instead of GNU-style
+        #[arg(long = "bsd")]
+        bsd: bool,
+    },
+    /// Write a Dublin Core XML record for the whole edition
+    DublinCore {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        #[arg(short = 'o', long = "output")]
+        output: std::path::PathBuf,
+
+        #[arg(long = "rights")]
+        rights: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CatalogueCommand {
+    /// Find editions; every option given must match, and text matches anywhere, ignoring case
+    Search {
+        /// Matches title, creator, gallery, artwork ID, keywords or file hashes
+        text: Option<String>,
+
+        #[arg(long = "title")]
+        title: Option<String>,
+
+        #[arg(long = "creator")]
+        creator: Option<String>,
+
+        #[arg(long = "keyword")]
+        keyword: Option<String>,
+
+        #[arg(long = "gallery")]
+        gallery: Option<String>,
+
+        /// Full hash or prefix of an artwork file or metadata file
+        #[arg(long = "file-hash")]
+        file_hash: Option<String>,
+
+        /// Print the matches as JSON instead of text
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// Record every *_metadata.json below a folder, e.g. a whole archive drive
+    Scan {
+        #[arg(short = 'p', long = "path")]
+        path: std::path::PathBuf,
+    },
+    /// Record a single metadata file
+    Add {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        /// Folder or archive containing the artwork files (defaults to the metadata file's folder)
+        #[arg(short = 'p', long = "path")]
+        path: Option<std::path::PathBuf>,
+    },
+    /// Drop a metadata file from the catalogue
+    Remove {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditCommand {
+    /// Register an edition for auditing
+    Add {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+
+        /// Folder or archive containing the artwork files (defaults to the metadata file's folder)
+        #[arg(short = 'p', long = "path")]
+        path: Option<std::path::PathBuf>,
+    },
+    /// Stop auditing an edition
+    Remove {
+        #[arg(short = 'f', long = "metadata-file")]
+        metadata_file: std::path::PathBuf,
+    },
+    /// List the registered editions
+    List,
+    /// Verify every registered edition and append the results to the audit log
+    Run {
+        /// Keep running and audit again after this many hours
+        #[arg(long = "every-hours")]
+        every_hours: Option<u64>,
+    },
+    /// Show every recorded check of a file, by path or by the file name recorded in the metadata
+    History {
+        #[arg(long = "file")]
+        file: std::path::PathBuf,
+    },
+    /// Check that no record of the audit log was altered, removed or inserted, and print the latest record hash
+    Check,
+}
+
+fn read_metadata(metadata_file: &std::path::Path) -> Result<Metadata, Error> {
+    let metadata_content = std::fs::read_to_string(metadata_file)?;
+    Ok(serde_json::from_str(&metadata_content)?)
+}
+
+fn write_qr_code(metadata_file: &std::path::Path, output: &std::path::Path) -> Result<(), Error> {
+    let payload = certificate_qr::QrPayload::from_metadata_file(metadata_file)?;
+    payload.write_to_file(output)?;
+    println!("QR payload: {}", payload.encode());
+    println!("QR code saved to: {}", output.display());
+    Ok(())
+}
+
+fn write_certificate(metadata_file: &std::path::Path, output: &std::path::Path) -> Result<(), Error> {
+    let metadata = read_metadata(metadata_file)?;
+    std::fs::write(output, certificate_qr::generate_certificate_svg(&metadata)?)?;
+    println!("Certificate saved to: {}", output.display());
+    Ok(())
+}
+
+fn verify_qr(metadata_file: &std::path::Path, payload: &str) -> Result<(), Error> {
+    println!("Verifying QR payload against: {}", metadata_file.display());
+
+    let verification = certificate_qr::verify_qr_payload(payload, metadata_file)?;
+    println!("Artwork ID: {}", if verification.artwork_id_matches { "✅ matches" } else { "❌ does not match" });
+    println!("Edition number: {}", if verification.edition_number_matches { "✅ matches" } else { "❌ does not match" });
+    println!("Metadata hash: {}", if verification.metadata_hash_matches { "✅ matches" } else { "❌ does not match" });
+    println!("Expected metadata hash: {}", verification.expected_metadata_hash);
+    println!("Actual metadata hash: {}", verification.actual_metadata_hash);
+
+    // A mismatch fails the command so scripts checking certificates see a non-zero exit status
+    if let Err(e) = verification.ensure_valid(metadata_file) {
+        eprintln!("❌ Certificate QR code does not match the metadata file!");
+        return Err(e.into());
+    }
+    println!("✅ Certificate QR code matches the metadata file!");
+    Ok(())
+}
+
+fn write_merkle_proof(metadata_file: &std::path::Path, file: &str, output: &std::path::Path) -> Result<(), Error> {
+    let metadata = read_metadata(metadata_file)?;
+
+    let proof = merkle::inclusion_proof(&metadata.artwork_files, file).ok_or_else(|| {
+        Error::new(std::io::ErrorKind::NotFound, format!("File not found in metadata: {}", file))
+    })?;
+    if metadata.merkle_root.as_ref() != Some(&proof.merkle_root) {
+        println!("Warning: Metadata file does not record this Merkle root, regenerate it to include one");
+    }
+
+    proof.save(output)?;
+    println!("Merkle root: {}", proof.merkle_root);
+    println!("Proof saved to: {}", output.display());
+    Ok(())
+}
+
+fn verify_merkle_proof(
+    proof_path: &std::path::Path,
+    file: Option<&std::path::Path>,
+    read_options: hasher::ReadOptions,
+) -> Result<(), Error> {
+    let proof = merkle::MerkleProof::load(proof_path)?;
+    println!("Merkle root: {}", proof.merkle_root);
+    println!("File: {} ({}/{})", proof.leaf.path, proof.leaf_index + 1, proof.leaf_count);
+
+    let mut is_valid = proof.verify();
+    println!("Proof: {}", if is_valid { "✅ valid" } else { "❌ invalid" });
+
+    if let Some(file) = file {
+        let actual_hash = hasher::UnifiedHasher::new_cli()
+            .with_read_options(read_options)
+            .hash_file(&file.to_string_lossy())?;
+        let actual_size = file.metadata()?.len();
+        let file_matches = actual_hash == proof.leaf.file_hash && actual_size == proof.leaf.file_size;
+        println!("File contents: {}", if file_matches { "✅ match" } else { "❌ do not match" });
+        is_valid &= file_matches;
+    }
+
+    // A failed check fails the command so scripts see a non-zero exit status
+    if !is_valid {
+        eprintln!("❌ File could not be shown to belong to the edition!");
+        return Err(metadata_generator::Error::signature(proof_path, "Merkle proof does not show the file belongs to the edition").into());
+    }
+    println!("✅ File belongs to the edition!");
+    Ok(())
+}
+
+/// Reads exported files with the global read options and retries, stopping on Ctrl-C
+fn export_generator(read_options: hasher::ReadOptions, retries: u32) -> MetadataGenerator {
+    MetadataGenerator::new_cli()
+        .with_read_options(read_options)
+        .with_read_retries(retries)
+        .with_cancellation(cancel_on_ctrl_c())
+}
+
+fn export(format: &ExportFormat, read_options: hasher::ReadOptions, retries: u32) -> Result<(), Error> {
+    match format {
+        ExportFormat::Car { path, metadata_file, output, update_metadata } => {
+            println!("Exporting CAR archive of: {}", path.display());
+            let export = car::export_car(path, metadata_file, output, &cancel_on_ctrl_c())?;
+            println!("Files: {}", export.file_count);
+            println!("Blocks: {}", export.block_count);
+            println!("CAR size: {} bytes", export.car_size);
+            println!("Root CID: {}", export.root_cid);
+            println!("CAR saved to: {}", output.display());
+
+            if *update_metadata {
+                car::write_root_cid(metadata_file, &export.root_cid)?;
+                println!("Root CID recorded in: {}", metadata_file.display());
+            }
+        }
+        ExportFormat::Erc721 { metadata_file, output, uri_base, external_url } => {
+            let options = erc721::Erc721Options {
+                uri_base: uri_base.clone(),
+                external_url: external_url.clone(),
+            };
+            let token = erc721::to_erc721(&read_metadata(metadata_file)?, &options);
+            std::fs::write(output, serde_json::to_string_pretty(&token)?)?;
+            println!("ERC-721 metadata saved to: {}", output.display());
+        }
+        ExportFormat::Tzip21 { metadata_file, output, path, uri_base, creators, rights } => {
+            let options = tzip21::Tzip21Options {
+                uri_base: uri_base.clone(),
+                // Artwork files live next to the metadata file unless told otherwise
+                base_folder: path.clone().or_else(|| metadata_file.parent().map(|parent| parent.to_path_buf())),
+                creators: creators.clone(),
+                rights: rights.clone(),
+            };
+            let token = tzip21::to_tzip21(&read_metadata(metadata_file)?, &options);
+            std::fs::write(output, serde_json::to_string_pretty(&token)?)?;
+            println!("TZIP-21 metadata saved to: {}", output.display());
+        }
+        ExportFormat::Jsonld { metadata_file, output, path, uri_base, url } => {
+            let options = jsonld::JsonLdOptions {
+                uri_base: uri_base.clone(),
+                base_folder: path.clone().or_else(|| metadata_file.parent().map(|parent| parent.to_path_buf())),
+                page_url: url.clone(),
+            };
+            let record = jsonld::to_json_ld(&read_metadata(metadata_file)?, &options)?;
+            std::fs::write(output, jsonld::render(&record, output)?)?;
+            println!("JSON-LD saved to: {}", output.display());
+        }
+        ExportFormat::Xmp { metadata_file, output, rights } => {
+            // Sidecars sit next to the artwork files unless told otherwise
+            let output_folder = output
+                .clone()
+                .or_else(|| metadata_file.parent().map(|parent| parent.to_path_buf()))
+                .unwrap_or_default();
+            let written = xmp::write_xmp_sidecars(&read_metadata(metadata_file)?, &output_folder, rights.as_deref())?;
+            for sidecar in &written {
+                println!("XMP sidecar saved to: {}", sidecar.display());
+            }
+        }
+        ExportFormat::Bagit { path, metadata_file, output } => {
+            let export = bagit::export_bag(&export_generator(read_options, retries), path, metadata_file, output)?;
+            println!("Payload files: {}", export.file_count);
+            println!("Payload size: {} bytes", export.payload_size);
+            println!("Bag saved to: {}", export.bag_path.display());
+        }
+        ExportFormat::Checksums { metadata_file, path, output, algorithm, bsd } => {
+            let algorithm = checksums::ChecksumAlgorithm::from_name(algorithm).ok_or_else(|| {
+                Error::new(std::io::ErrorKind::InvalidInput, format!("Unknown checksum algorithm: {}", algorithm))
+            })?;
+            let base_folder = path
+                .clone()
+                .or_else(|| metadata_file.parent().map(|parent| parent.to_path_buf()))
+                .unwrap_or_default();
+            let output = output.clone().unwrap_or_else(|| base_folder.join(algorithm.default_file_name()));
+            let style = if *bsd { checksums::ManifestStyle::Bsd } else { checksums::ManifestStyle::Gnu };
+            let generator = export_generator(read_options, retries);
+            let count = checksums::write_manifest(&generator, &read_metadata(metadata_file)?, &base_folder, algorithm, style, &output)?;
+            println!("{} checksums saved to: {}", count, output.display());
+        }
+        ExportFormat::DublinCore { metadata_file, output, rights } => {
+            let record = xmp::dublin_core_record(&read_metadata(metadata_file)?, rights.as_deref());
+            std::fs::write(output, record)?;
+            println!("Dublin Core record saved to: {}", output.display());
+        }
     }
     Ok(())
 }
 
 fn main() -> Result<(), Error> {
     let args = Cli::parse();
-    
-    if args.verify {
-        verify_metadata()
-    } else {
-        check_for_metadata_file()
+
+    let result = match &args.command {
+        Some(Command::Qr { metadata_file, output }) => write_qr_code(metadata_file, output),
+        Some(Command::Certificate { metadata_file, output }) => write_certificate(metadata_file, output),
+        Some(Command::VerifyQr { metadata_file, payload }) => verify_qr(metadata_file, payload),
+        Some(Command::Export { format }) => export(format, args.read_options(), args.retries),
+        Some(Command::MerkleProof { metadata_file, file, output }) => write_merkle_proof(metadata_file, file, output),
+        Some(Command::Update { path, metadata_file }) => {
+            update_metadata(
+                path.as_deref(),
+                metadata_file,
+                args.ipfs_cid,
+                args.hash_cache,
+                args.rehash,
+                args.read_options(),
+                !args.no_catalogue,
+            )
+        }
+        Some(Command::Diff { old, new, json, output }) => diff_metadata(old, new, *json, output.as_deref()),
+        Some(Command::Package { path, metadata_file, output }) => package(path, metadata_file, output),
+        Some(Command::VerifyArchive { archive }) => verify_archive(archive, &ReportOptions::from_args(&args)?),
+        Some(Command::VerifyBag { bag }) => {
+            verify_bag(bag, &ReportOptions::from_args(&args)?, args.read_options(), args.retries)
+        }
+        Some(Command::VerifyProof { proof, file }) => verify_merkle_proof(proof, file.as_deref(), args.read_options()),
+        Some(Command::Watch { metadata_file, path, alert_log }) => {
+            watch_folder(metadata_file, path.as_deref(), alert_log.as_deref(), args.read_options(), args.retries)
+        }
+        Some(Command::Catalogue { database, action }) => run_catalogue_command(database.as_deref(), action),
+        Some(Command::Audit { registry, log, action }) => {
+            run_audit_command(registry.as_deref(), log.as_deref(), action, args.read_options(), args.retries)
+        }
+        None if args.verify => verify_metadata(),
+        None => check_for_metadata_file(),
+    };
+
+    // Other cancelled passes write nothing, so report them apart from failures
+    match result {
+        Err(e) if cancel::is_cancelled(&e) => {
+            eprintln!("⏹️ Cancelled: nothing was written");
+            std::process::exit(130);
+        }
+        result => result,
     }
 }   
 
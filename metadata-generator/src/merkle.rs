@@ -0,0 +1,224 @@
+use std::{fs, path::Path};
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, IoResultExt, Result};
+use crate::metadata_generator::ArtworkFile;
+
+/// Domain separation prefixes so a leaf can never be mistaken for an inner node
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// A single (path, hash, size) entry committed to by the edition Merkle root
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MerkleLeaf {
+    pub path: String,
+    pub file_hash: String,
+    pub file_size: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SiblingPosition {
+    Left,
+    Right,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProofStep {
+    pub hash: String,
+    pub position: SiblingPosition,
+}
+
+/// Inclusion proof showing a single file belongs to an edition without the full manifest
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MerkleProof {
+    pub merkle_root: String,
+    pub leaf_index: usize,
+    pub leaf_count: usize,
+    pub leaf: MerkleLeaf,
+    pub steps: Vec<ProofStep>,
+}
+
+impl MerkleLeaf {
+    pub fn from_artwork_file(artwork_file: &ArtworkFile) -> Self {
+        Self {
+            path: artwork_file.path.clone(),
+            file_hash: artwork_file.file_hash.clone(),
+            file_size: artwork_file.file_size,
+        }
+    }
+
+    fn hash(&self) -> blake3::Hash {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&[LEAF_PREFIX]);
+        hasher.update(&(self.path.len() as u64).to_le_bytes());
+        hasher.update(self.path.as_bytes());
+        hasher.update(&(self.file_hash.len() as u64).to_le_bytes());
+        hasher.update(self.file_hash.as_bytes());
+        hasher.update(&self.file_size.to_le_bytes());
+        hasher.finalize()
+    }
+}
+
+fn hash_node(left: &blake3::Hash, right: &blake3::Hash) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalize()
+}
+
+/// Sorts leaves by path so the root does not depend on directory listing order
+fn sorted_leaves(leaves: &[MerkleLeaf]) -> Vec<MerkleLeaf> {
+    let mut sorted = leaves.to_vec();
+    sorted.sort_by(|a, b| a.path.cmp(&b.path));
+    sorted
+}
+
+/// Builds every level of the tree, from the leaf hashes up to the root
+/// An odd node at the end of a level is promoted unchanged to the next level
+fn build_levels(leaves: &[MerkleLeaf]) -> Vec<Vec<blake3::Hash>> {
+    let mut levels = vec![leaves.iter().map(MerkleLeaf::hash).collect::<Vec<_>>()];
+
+    while levels.last().map(|level| level.len() > 1).unwrap_or(false) {
+        let level = levels.last().unwrap();
+        let next = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+
+    levels
+}
+
+/// Computes the Merkle root over the given leaves, or None for an empty edition
+pub fn merkle_root(leaves: &[MerkleLeaf]) -> Option<String> {
+    if leaves.is_empty() {
+        return None;
+    }
+    let levels = build_levels(&sorted_leaves(leaves));
+    levels.last().and_then(|level| level.first()).map(|root| root.to_hex().to_string())
+}
+
+/// Computes the Merkle root over the artwork files recorded in a metadata document
+pub fn merkle_root_for_files(artwork_files: &[ArtworkFile]) -> Option<String> {
+    let leaves: Vec<MerkleLeaf> = artwork_files.iter().map(MerkleLeaf::from_artwork_file).collect();
+    merkle_root(&leaves)
+}
+
+/// Produces an inclusion proof for the file with the given path or file name
+pub fn inclusion_proof(artwork_files: &[ArtworkFile], file: &str) -> Option<MerkleProof> {
+    let leaves: Vec<MerkleLeaf> = artwork_files.iter().map(MerkleLeaf::from_artwork_file).collect();
+    let leaves = sorted_leaves(&leaves);
+    let leaf_index = leaves.iter().position(|leaf| {
+        leaf.path == file || leaf.path.trim_start_matches("./") == file.trim_start_matches("./")
+    })?;
+
+    let levels = build_levels(&leaves);
+    let mut steps = Vec::new();
+    let mut index = leaf_index;
+
+    for level in &levels[..levels.len() - 1] {
+        let sibling_index = index ^ 1;
+        // A promoted odd node has no sibling at this level
+        if sibling_index < level.len() {
+            steps.push(ProofStep {
+                hash: level[sibling_index].to_hex().to_string(),
+                position: if sibling_index < index { SiblingPosition::Left } else { SiblingPosition::Right },
+            });
+        }
+        index /= 2;
+    }
+
+    Some(MerkleProof {
+        merkle_root: levels.last()?.first()?.to_hex().to_string(),
+        leaf_index,
+        leaf_count: leaves.len(),
+        leaf: leaves[leaf_index].clone(),
+        steps,
+    })
+}
+
+impl MerkleProof {
+    /// Recomputes the root from the leaf and sibling hashes and compares it to the recorded root
+    pub fn verify(&self) -> bool {
+        let mut current = self.leaf.hash();
+        for step in &self.steps {
+            let sibling = match blake3::Hash::from_hex(&step.hash) {
+                Ok(hash) => hash,
+                Err(_) => return false,
+            };
+            current = match step.position {
+                SiblingPosition::Left => hash_node(&sibling, &current),
+                SiblingPosition::Right => hash_node(&current, &sibling),
+            };
+        }
+        current.to_hex().as_str() == self.merkle_root
+    }
+
+    pub fn load(proof_path: &Path) -> Result<Self> {
+        let proof_content = fs::read_to_string(proof_path).with_path(proof_path)?;
+        serde_json::from_str(&proof_content).map_err(|e| Error::parse(proof_path, e))
+    }
+
+    pub fn save(&self, proof_path: &Path) -> Result<()> {
+        let proof_content = serde_json::to_string_pretty(self).map_err(|e| Error::io(proof_path, e.into()))?;
+        fs::write(proof_path, proof_content).with_path(proof_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artwork_files(count: usize) -> Vec<ArtworkFile> {
+        (0..count)
+            .map(|i| ArtworkFile {
+                path: format!("./art/file_{i}.png"),
+                file_name: format!("file_{i}.png"),
+                file_hash: format!("{i:064x}"),
+                file_size: i as u64 * 100,
+                format: "png".to_string(),
+                ipfs_cid: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn proofs_verify_for_every_leaf() {
+        for count in [1, 2, 5, 8] {
+            let files = artwork_files(count);
+            let root = merkle_root_for_files(&files).unwrap();
+            for file in &files {
+                let proof = inclusion_proof(&files, &file.path).unwrap();
+                assert_eq!(proof.merkle_root, root);
+                assert!(proof.verify(), "proof for {} of {count} leaves", file.path);
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_leaf_fails_verification() {
+        let files = artwork_files(5);
+        let mut proof = inclusion_proof(&files, "art/file_2.png").unwrap();
+        proof.leaf.file_hash = format!("{:064x}", 99);
+        assert!(!proof.verify());
+
+        let mut proof = inclusion_proof(&files, "art/file_2.png").unwrap();
+        proof.leaf.file_size += 1;
+        assert!(!proof.verify());
+    }
+
+    #[test]
+    fn root_ignores_listing_order() {
+        let files = artwork_files(6);
+        let mut reversed = files.clone();
+        reversed.reverse();
+        assert_eq!(merkle_root_for_files(&files), merkle_root_for_files(&reversed));
+        assert_eq!(merkle_root_for_files(&[]), None);
+    }
+}
@@ -1,14 +1,39 @@
-use std::{fs::{self, File}, io::{BufWriter, Write}, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
 use serde::{Deserialize, Serialize};
 use crate::constants::should_ignore_file;
-use crate::hasher::UnifiedHasher;
+use crate::xmp::is_generated_sidecar;
+use crate::hasher::{ReadOptions, UnifiedHasher, PROGRESS_STEP};
+use crate::hash_cache::HashCache;
 use crate::file_analyzer::analyze_file;
-use memmap;
-use blake3;
+use crate::merkle::{merkle_root, merkle_root_for_files, MerkleLeaf};
+use crate::ipfs;
+use crate::checksums::{self, ChecksumAlgorithm};
+use crate::archive::{self, ArchiveContents};
+use crate::cancel::CancellationToken;
+use crate::checkpoint::VerificationCheckpoint;
+use crate::error::{Error, IoResultExt, Result};
+use crate::events::{CertificateOutcome, FileChange, FileOutcome, ProgressEvent, ProgressObserver};
+use crate::watch::FolderWatcher;
+
+/// How often a resumable verification writes its checkpoint while files complete
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often watch mode wakes up to check for cancellation while waiting for changes
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Pause before the first retry of a failed read; each further attempt waits one step longer
+const RETRY_DELAY: Duration = Duration::from_secs(1);
 
 /// Detects certificate of authenticity PDF files in a certificate folder
 /// Returns the relative path to the first PDF file found, or None if no PDF files exist
-pub fn detect_certificate_of_authenticity(folder_path: &PathBuf) -> Option<String> {
+pub fn detect_certificate_of_authenticity(folder_path: &Path) -> Option<String> {
     let certificate_folder = folder_path.join("certificate");
     
     if !certificate_folder.exists() || !certificate_folder.is_dir() {
@@ -17,16 +42,14 @@ pub fn detect_certificate_of_authenticity(folder_path: &PathBuf) -> Option<Strin
     
     // Look for any PDF file in the certificate folder
     if let Ok(entries) = fs::read_dir(&certificate_folder) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(extension) = path.extension() {
-                        if extension.to_string_lossy().to_lowercase() == "pdf" {
-                            // Return relative path to the PDF file
-                            let file_name = path.file_name().unwrap().to_string_lossy().to_string();
-                            return Some(format!("./certificate/{}", file_name));
-                        }
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() {
+                if let Some(extension) = path.extension() {
+                    if extension.to_string_lossy().to_lowercase() == "pdf" {
+                        // Return relative path to the PDF file
+                        let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+                        return Some(format!("./certificate/{}", file_name));
                     }
                 }
             }
@@ -43,6 +66,8 @@ pub struct ArtworkFile {
     pub file_hash: String,
     pub file_size: u64,
     pub format: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipfs_cid: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -65,6 +90,76 @@ pub struct Metadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub certificate_hash: Option<String>,
     pub artwork_files: Vec<ArtworkFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merkle_root: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipfs_root_cid: Option<String>,
+}
+
+/// Fields covered by `Metadata::canonical_hash`
+/// Kept fixed so optional fields written later (IPFS root, Merkle root) do not change the hash
+#[derive(Serialize)]
+struct CanonicalMetadata<'a> {
+    artwork_id: &'a str,
+    artwork_title: &'a str,
+    artwork_creator: &'a str,
+    year_of_creation: i32,
+    edition_number: i32,
+    total_editions: i32,
+    certificate_hash: Option<&'a str>,
+    artwork_files: Vec<CanonicalFile<'a>>,
+}
+
+#[derive(Serialize)]
+struct CanonicalFile<'a> {
+    file_name: &'a str,
+    file_hash: &'a str,
+    file_size: u64,
+}
+
+impl Metadata {
+    /// Hashes the identity of the edition and its file hashes using BLAKE3
+    /// Only a fixed set of fields is covered, so exports that add optional fields keep printed certificates valid
+    pub fn canonical_hash(&self) -> Result<String> {
+        let canonical = CanonicalMetadata {
+            artwork_id: &self.artwork_id,
+            artwork_title: &self.artwork_title,
+            artwork_creator: &self.artwork_creator,
+            year_of_creation: self.year_of_creation,
+            edition_number: self.edition_number,
+            total_editions: self.total_editions,
+            certificate_hash: self.certificate_hash.as_deref(),
+            artwork_files: self.artwork_files.iter()
+                .map(|file| CanonicalFile {
+                    file_name: &file.file_name,
+                    file_hash: &file.file_hash,
+                    file_size: file.file_size,
+                })
+                .collect(),
+        };
+        let canonical = serde_json::to_vec(&canonical)?;
+        Ok(blake3::hash(&canonical).to_hex().to_string())
+    }
+
+    /// Rights statement used by exports when none is given
+    pub fn default_rights(&self) -> String {
+        format!("© {} {}. All rights reserved.", self.year_of_creation, self.artwork_creator)
+    }
+
+    /// Builds a link to an artwork file for external metadata standards
+    /// Uses the given URI base, then the recorded IPFS root or file CID, then the relative path
+    pub fn asset_uri(&self, artwork_file: &ArtworkFile, uri_base: Option<&str>) -> String {
+        if let Some(uri_base) = uri_base {
+            return format!("{}/{}", uri_base.trim_end_matches('/'), artwork_file.file_name);
+        }
+        if let Some(root_cid) = &self.ipfs_root_cid {
+            return format!("ipfs://{}/{}", root_cid, artwork_file.file_name);
+        }
+        if let Some(file_cid) = &artwork_file.ipfs_cid {
+            return format!("ipfs://{}", file_cid);
+        }
+        artwork_file.path.clone()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -86,115 +181,187 @@ pub struct VerificationReport {
     pub metadata_file_valid: bool,
     pub certificate_valid: Option<bool>,
     pub certificate_hash: Option<String>,
+    #[serde(default)]
+    pub merkle_root_valid: Option<bool>,
     pub overall_valid: bool,
 }
 
-#[allow(dead_code)]
-pub enum ProgressCallback {
-    Cli(Box<dyn Fn(String) + Send>),
-    Gui(Box<dyn Fn(String, f32, f32) + Send>), // message, file_progress, overall_progress
-    None,
+/// Outcome of refreshing an existing metadata file against its folder
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UpdateSummary {
+    pub metadata_path: PathBuf,
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+    pub unchanged: usize,
+    /// Unchanged files that were re-hashed because they were modified after the metadata file was written
+    pub touched: Vec<String>,
+    pub certificate_changed: bool,
+}
+
+impl UpdateSummary {
+    pub fn has_changes(&self) -> bool {
+        !self.added.is_empty() || !self.changed.is_empty() || !self.removed.is_empty() || self.certificate_changed
+    }
+}
+
+/// A file watch mode guards, with what it looked like when last checked
+struct GuardedFile {
+    file_name: String,
+    expected_hash: String,
+    last_seen: (FileChange, Option<String>),
+}
+
+impl GuardedFile {
+    /// State of a file that could be hashed, or was missing when `hash` is None
+    fn seen(expected_hash: &str, hash: Option<String>) -> (FileChange, Option<String>) {
+        match hash {
+            Some(hash) if hash == expected_hash => (FileChange::Restored, Some(hash)),
+            Some(hash) => (FileChange::Modified, Some(hash)),
+            None => (FileChange::Removed, None),
+        }
+    }
 }
 
 pub struct MetadataGenerator {
     hasher: UnifiedHasher,
-    progress_callback: ProgressCallback,
+    observer: Box<dyn ProgressObserver>,
+    cancellation: CancellationToken,
+    compute_ipfs_cid: bool,
+    checkpoint: bool,
+    read_retries: u32,
 }
 
 impl MetadataGenerator {
-    #[allow(dead_code)]
     pub fn new_cli() -> Self {
         Self {
             hasher: UnifiedHasher::new_cli(),
-            progress_callback: ProgressCallback::None,
+            observer: Box::new(|_: &ProgressEvent| {}),
+            cancellation: CancellationToken::new(),
+            compute_ipfs_cid: false,
+            checkpoint: false,
+            read_retries: 0,
         }
     }
 
     pub fn new_gui() -> Self {
         Self {
             hasher: UnifiedHasher::new_gui(),
-            progress_callback: ProgressCallback::None,
+            observer: Box::new(|_: &ProgressEvent| {}),
+            cancellation: CancellationToken::new(),
+            compute_ipfs_cid: false,
+            checkpoint: false,
+            read_retries: 0,
         }
     }
 
-    pub fn with_progress_callback(mut self, callback: ProgressCallback) -> Self {
-        self.progress_callback = callback;
+    /// Sends progress events of every pass to the observer
+    pub fn with_observer(mut self, observer: impl ProgressObserver + 'static) -> Self {
+        self.observer = Box::new(observer);
+        self
+    }
+
+    /// Lets another thread stop a pass; a cancelled pass returns `Error::Cancelled` and never writes
+    /// a metadata file
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.hasher = self.hasher.with_cancellation(cancellation.clone());
+        self.cancellation = cancellation;
+        self
+    }
+
+    pub(crate) fn hasher(&self) -> &UnifiedHasher {
+        &self.hasher
+    }
+
+    /// Chooses between memory-mapped and streaming reads, e.g. to force streaming from an external drive
+    pub fn with_read_options(mut self, read_options: ReadOptions) -> Self {
+        self.hasher = self.hasher.with_read_options(read_options);
         self
     }
 
-    #[allow(dead_code)]
+    /// Reuse hashes of unchanged files when generating; verification never reads the cache
+    pub fn with_hash_cache(mut self, cache: HashCache) -> Self {
+        self.hasher = self.hasher.with_cache(cache);
+        self
+    }
+
+    /// Also record the IPFS CIDv1 of each artwork file (requires an extra SHA-256 pass)
+    pub fn with_ipfs_cid(mut self, compute_ipfs_cid: bool) -> Self {
+        self.compute_ipfs_cid = compute_ipfs_cid;
+        self
+    }
+
+    /// Keep a checkpoint next to the metadata file while verifying, so a run that is cancelled or
+    /// crashes resumes from the files already read; the checkpoint is removed once a run completes
+    pub fn with_checkpoint(mut self, checkpoint: bool) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
+    /// Read a file up to this many more times after a transient I/O error before reporting it as failed
+    pub fn with_read_retries(mut self, read_retries: u32) -> Self {
+        self.read_retries = read_retries;
+        self
+    }
+
+    /// Hashes every artwork file in the folder and writes `<title>_metadata.json` next to them
+    /// CLI and GUI share this single pass; progress is pushed to the observer as each file is hashed
     pub fn generate_metadata(
         &self,
-        folder_path: &PathBuf,
+        folder_path: &Path,
         metadata: &Metadata,
-    ) -> std::io::Result<PathBuf> {
-        let entries: Vec<_> = fs::read_dir(folder_path)?.collect();
-        let total_files = entries.len();
-        let mut processed_files = 0;
+    ) -> Result<PathBuf> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(folder_path).with_path(folder_path)? {
+            let path = entry.with_path(folder_path)?.path();
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if path.is_file() && !should_ignore_file(&file_name) && !is_generated_sidecar(&path) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        let total_files = paths.len();
+        self.emit(ProgressEvent::ScanStarted { total_files });
 
         let mut output_metadata = metadata.clone();
         output_metadata.artwork_files.clear();
 
-        for (index, entry) in entries.into_iter().enumerate() {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if path.is_file() {
-                let file_name = path.file_name().unwrap().to_string_lossy().to_string();
-                let relative_path = format!("./{}", file_name);
-                
-                // Skip ignored files
-                if should_ignore_file(&file_name) {
-                    continue;
-                }
-                
-                // Report progress
-                match &self.progress_callback {
-                    ProgressCallback::Cli(callback) => {
-                        callback(format!("Hashing file: {}", file_name));
-                    }
-                    ProgressCallback::Gui(callback) => {
-                        let overall_progress = index as f32 / total_files as f32;
-                        callback(file_name.clone(), 0.0, overall_progress);
-                    }
-                    ProgressCallback::None => {}
-                }
+        for (index, path) in paths.iter().enumerate() {
+            self.cancellation.check().with_path(path)?;
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            self.emit(ProgressEvent::FileStarted { file_name: file_name.clone(), index, total_files });
 
-                // Hash the file (use absolute path for hashing)
-                let absolute_path = path.to_string_lossy().to_string();
-                let hash = self.hasher.hash_file(&absolute_path)?;
-                processed_files += 1;
+            // Unchanged files are served from the hash cache; the rest are hashed with progress updates
+            let absolute_path = path.to_string_lossy().to_string();
+            let hash = self.hasher.hash_file_cached(&absolute_path, &mut |bytes_hashed, file_size| {
+                self.emit(ProgressEvent::BytesHashed { file_name: file_name.clone(), bytes_hashed, file_size, index, total_files });
+            })?;
 
-                // Analyze file to extract metadata
-                let file_metadata = analyze_file(&path).unwrap_or_else(|_| {
-                    // Fallback if analysis fails
-                    crate::file_analyzer::FileMetadata {
-                        format: path.extension().unwrap_or_default().to_string_lossy().to_string().to_uppercase(),
-                    }
-                });
+            // Analyze file to extract metadata
+            let file_metadata = analyze_file(path).unwrap_or_else(|_| {
+                // Fallback if analysis fails
+                crate::file_analyzer::FileMetadata {
+                    format: path.extension().unwrap_or_default().to_string_lossy().to_string().to_uppercase(),
+                }
+            });
 
-                let af = ArtworkFile {
-                    path: relative_path,
-                    file_name: file_name.clone(),
-                    file_hash: hash,
-                    file_size: path.metadata()?.len(),
-                    format: file_metadata.format,
-                };
-                
-                output_metadata.artwork_files.push(af);
+            // Compute the IPFS CID offline if requested
+            let ipfs_cid = if self.compute_ipfs_cid {
+                Some(ipfs::file_cid(path, &self.cancellation)?)
+            } else {
+                None
+            };
 
-                // Report completion
-                match &self.progress_callback {
-                    ProgressCallback::Cli(callback) => {
-                        callback(format!("Completed: {} ({}/{})", file_name, processed_files, total_files));
-                    }
-                    ProgressCallback::Gui(callback) => {
-                        let overall_progress = processed_files as f32 / total_files as f32;
-                        callback(file_name, 1.0, overall_progress);
-                    }
-                    ProgressCallback::None => {}
-                }
-            }
+            output_metadata.artwork_files.push(ArtworkFile {
+                path: format!("./{}", file_name),
+                file_name: file_name.clone(),
+                file_hash: hash,
+                file_size: path.metadata().with_path(path)?.len(),
+                format: file_metadata.format,
+                ipfs_cid,
+            });
+
+            self.emit(ProgressEvent::FileCompleted { file_name, index, total_files, outcome: FileOutcome::Hashed });
         }
 
         // Hash certificate if it exists
@@ -204,205 +371,314 @@ impl MetadataGenerator {
                 match self.hasher.hash_file(&certificate_full_path.to_string_lossy()) {
                     Ok(certificate_hash) => {
                         output_metadata.certificate_hash = Some(certificate_hash);
-                        
-                        // Report certificate hashing
-                        match &self.progress_callback {
-                            ProgressCallback::Cli(callback) => {
-                                callback(format!("Certificate hashed: {}", certificate_path));
-                            }
-                            ProgressCallback::Gui(callback) => {
-                                callback("Certificate".to_string(), 1.0, 1.0);
-                            }
-                            ProgressCallback::None => {}
-                        }
-                    }
-                    Err(e) => {
-                        // Log error but continue
-                        match &self.progress_callback {
-                            ProgressCallback::Cli(callback) => {
-                                callback(format!("Warning: Could not hash certificate: {}", e));
-                            }
-                            ProgressCallback::Gui(callback) => {
-                                callback("Certificate error".to_string(), 1.0, 1.0);
-                            }
-                            ProgressCallback::None => {}
-                        }
+                        self.emit(ProgressEvent::CertificateChecked {
+                            certificate_path: certificate_path.clone(),
+                            outcome: CertificateOutcome::Hashed,
+                            error: None,
+                        });
                     }
+                    Err(Error::Cancelled) => return Err(Error::Cancelled),
+                    // Log error but continue
+                    Err(e) => self.emit(ProgressEvent::CertificateChecked {
+                        certificate_path: certificate_path.clone(),
+                        outcome: CertificateOutcome::Failed,
+                        error: Some(e.message()),
+                    }),
                 }
             }
         }
 
+        // Commit to the whole set of files with a single Merkle root
+        output_metadata.merkle_root = merkle_root_for_files(&output_metadata.artwork_files);
+
+        self.hasher.save_cache()?;
+        self.emit(ProgressEvent::Finished { processed_files: total_files, failed_files: 0 });
+
         // Save metadata to file
         let file_name = format!("{}_metadata.json", metadata.artwork_title.replace(' ', "_"));
         let output = folder_path.join(file_name);
-        let file = File::create(&output)?;
-        let mut writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(&mut writer, &output_metadata)?;
-        writer.flush()?;
+        write_metadata(&output, &output_metadata)?;
 
         Ok(output)
     }
 
-    pub fn generate_metadata_async(
+    pub(crate) fn emit(&self, event: ProgressEvent) {
+        self.observer.on_event(&event);
+    }
+
+    /// Hashes a file for verification with the given algorithm, retrying reads that fail for reasons
+    /// other than the file being missing or unreadable, e.g. a USB drive that dropped off the bus for a moment
+    pub(crate) fn hash_with_retries(
         &self,
-        folder_path: &PathBuf,
-        metadata: &Metadata,
-    ) -> std::io::Result<PathBuf> {
-        let entries: Vec<_> = fs::read_dir(folder_path)?.collect();
-        let total_files = entries.len();
-        let mut processed_files = 0;
+        algorithm: ChecksumAlgorithm,
+        file_path: &Path,
+        file_name: &str,
+        index: usize,
+        total_files: usize,
+    ) -> Result<String> {
+        self.with_retries(file_path, file_name, index, total_files, || {
+            algorithm.hash_file_with_progress(file_path, &self.hasher, &mut |bytes_hashed, file_size| {
+                self.emit(ProgressEvent::BytesHashed { file_name: file_name.to_string(), bytes_hashed, file_size, index, total_files });
+            })
+        })
+    }
 
-        let mut output_metadata = metadata.clone();
-        output_metadata.artwork_files.clear();
+    /// Opens a file for a copy driven by other code, e.g. an archive writer, so reading it stops once the
+    /// pass is cancelled and is reported as `BytesHashed`
+    pub(crate) fn progress_reader<'a>(
+        &'a self,
+        path: &Path,
+        file_name: &'a str,
+        index: usize,
+        total_files: usize,
+    ) -> io::Result<ProgressReader<'a>> {
+        let file = File::open(path)?;
+        let file_size = file.metadata()?.len();
+        Ok(ProgressReader { generator: self, file, file_name, index, total_files, file_size, bytes_read: 0, bytes_reported: 0 })
+    }
 
-        for (index, entry) in entries.into_iter().enumerate() {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if path.is_file() {
-                let file_name = path.file_name().unwrap().to_string_lossy().to_string();
-                let relative_path = format!("./{}", file_name);
-                
-                // Skip ignored files
-                if should_ignore_file(&file_name) {
-                    continue;
-                }
-                
-                // Report progress
-                match &self.progress_callback {
-                    ProgressCallback::Cli(callback) => {
-                        callback(format!("Hashing file: {}", file_name));
-                    }
-                    ProgressCallback::Gui(callback) => {
-                        let overall_progress = index as f32 / total_files as f32;
-                        callback(file_name.clone(), 0.0, overall_progress);
-                    }
-                    ProgressCallback::None => {}
+    /// Runs a read of `file_path` again after transient I/O errors, up to `read_retries` times
+    fn with_retries<T>(
+        &self,
+        file_path: &Path,
+        file_name: &str,
+        index: usize,
+        total_files: usize,
+        mut read: impl FnMut() -> Result<T>,
+    ) -> Result<T> {
+        let mut attempt = 0;
+        loop {
+            match read() {
+                Err(Error::Io { source, .. })
+                    if attempt < self.read_retries
+                        && !matches!(source.kind(), std::io::ErrorKind::NotFound | std::io::ErrorKind::PermissionDenied) =>
+                {
+                    attempt += 1;
+                    self.emit(ProgressEvent::FileRetrying {
+                        file_name: file_name.to_string(),
+                        index,
+                        total_files,
+                        attempt,
+                        error: source.to_string(),
+                    });
+                    self.pause(RETRY_DELAY * attempt).with_path(file_path)?;
                 }
+                result => return result,
+            }
+        }
+    }
 
-                // Start async hashing (use absolute path for hashing)
-                let absolute_path = path.to_string_lossy().to_string();
-                self.hasher.hash_file_async(&absolute_path);
-                
-                // Wait for hash to complete while updating progress
-                let hash = loop {
-                    // Update file progress for GUI
-                    if let ProgressCallback::Gui(callback) = &self.progress_callback {
-                        let file_progress = self.hasher.get_file_progress();
-                        let overall_progress = index as f32 / total_files as f32;
-                        callback(file_name.clone(), file_progress, overall_progress);
-                    }
-                    
-                    // Check if hash is complete
-                    if let Some(result) = self.hasher.get_result() {
-                        match result {
-                            Ok(hash) => break hash,
-                            Err(e) => return Err(e),
-                        }
-                    }
-                    
-                    std::thread::sleep(std::time::Duration::from_millis(50));
-                };
-                
-                processed_files += 1;
-
-                // Analyze file to extract metadata
-                let file_metadata = analyze_file(&path).unwrap_or_else(|_| {
-                    // Fallback if analysis fails
-                    crate::file_analyzer::FileMetadata {
-                        format: path.extension().unwrap_or_default().to_string_lossy().to_string().to_uppercase(),
-                    }
-                });
+    /// Loads the checkpoint kept next to a metadata file or manifest when checkpoints are on
+    fn open_checkpoint(
+        &self,
+        use_checkpoint: bool,
+        metadata_path: &Path,
+        metadata_file_hash: &str,
+        base_folder: &Path,
+        total_files: usize,
+    ) -> Option<(PathBuf, VerificationCheckpoint)> {
+        let checkpoint_path = use_checkpoint.then(|| VerificationCheckpoint::path_for(metadata_path))?;
+        let checkpoint = VerificationCheckpoint::load(&checkpoint_path, metadata_file_hash, base_folder);
+        if !checkpoint.is_empty() {
+            self.emit(ProgressEvent::ResumedFromCheckpoint { completed_files: checkpoint.len(), total_files });
+        }
+        Some((checkpoint_path, checkpoint))
+    }
 
-                let af = ArtworkFile {
-                    path: relative_path,
-                    file_name: file_name.clone(),
-                    file_hash: hash,
-                    file_size: path.metadata()?.len(),
-                    format: file_metadata.format,
-                };
-                
-                output_metadata.artwork_files.push(af);
+    /// Sleeps in short steps so a cancelled pass does not wait out the whole delay
+    fn pause(&self, delay: Duration) -> std::io::Result<()> {
+        let deadline = Instant::now() + delay;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            self.cancellation.check()?;
+            thread::sleep(remaining.min(Duration::from_millis(100)));
+        }
+        self.cancellation.check()
+    }
+    /// Refreshes an existing metadata file in place: new and changed files are hashed, entries for
+    /// deleted files are dropped and every descriptive field is kept as it was
+    /// A file counts as unchanged when its size matches and it was not modified after the metadata file
+    /// was written; the file's content only changes when a file did, so its fingerprint stays stable,
+    /// but it is saved again after touched files were re-hashed so they are not re-hashed next time
+    pub fn update_metadata(&self, folder_path: &Path, metadata_path: &Path) -> Result<UpdateSummary> {
+        let mut metadata = read_metadata_file(metadata_path)?;
+        let written_at = fs::metadata(metadata_path).and_then(|info| info.modified()).with_path(metadata_path)?;
 
-                // Report completion
-                match &self.progress_callback {
-                    ProgressCallback::Cli(callback) => {
-                        callback(format!("Completed: {} ({}/{})", file_name, processed_files, total_files));
-                    }
-                    ProgressCallback::Gui(callback) => {
-                        let overall_progress = processed_files as f32 / total_files as f32;
-                        callback(file_name, 1.0, overall_progress);
-                    }
-                    ProgressCallback::None => {}
+        let mut file_names = Vec::new();
+        for entry in fs::read_dir(folder_path).with_path(folder_path)? {
+            let path = entry.with_path(folder_path)?.path();
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if path.is_file() && !should_ignore_file(&file_name) && !is_generated_sidecar(&path) {
+                file_names.push(file_name);
+            }
+        }
+        file_names.sort();
+
+        let mut summary = UpdateSummary {
+            metadata_path: metadata_path.to_path_buf(),
+            ..UpdateSummary::default()
+        };
+        let previous_files = std::mem::take(&mut metadata.artwork_files);
+        let total_files = file_names.len();
+        self.emit(ProgressEvent::ScanStarted { total_files });
+
+        // Keep the recorded order for files that are still present, then append new files by name
+        let mut ordered_names: Vec<String> = previous_files
+            .iter()
+            .map(|artwork_file| artwork_file.file_name.clone())
+            .filter(|file_name| file_names.contains(file_name))
+            .collect();
+        for file_name in &file_names {
+            if !ordered_names.contains(file_name) {
+                ordered_names.push(file_name.clone());
+            }
+        }
+        for artwork_file in &previous_files {
+            if !file_names.contains(&artwork_file.file_name) {
+                summary.removed.push(artwork_file.file_name.clone());
+            }
+        }
+
+        for (index, file_name) in ordered_names.into_iter().enumerate() {
+            let path = folder_path.join(&file_name);
+            self.cancellation.check().with_path(&path)?;
+            let file_info = path.metadata().with_path(&path)?;
+            let previous = previous_files.iter().find(|artwork_file| artwork_file.file_name == file_name);
+
+            if let Some(previous) = previous {
+                let modified_since = file_info.modified().map(|modified| modified > written_at).unwrap_or(true);
+                if previous.file_size == file_info.len() && !modified_since {
+                    metadata.artwork_files.push(previous.clone());
+                    summary.unchanged += 1;
+                    continue;
+                }
+            }
+
+            self.emit(ProgressEvent::FileStarted { file_name: file_name.clone(), index, total_files });
+            let absolute_path = path.to_string_lossy().to_string();
+            let hash = self.hasher.hash_file_cached(&absolute_path, &mut |bytes_hashed, file_size| {
+                self.emit(ProgressEvent::BytesHashed { file_name: file_name.clone(), bytes_hashed, file_size, index, total_files });
+            })?;
+            self.emit(ProgressEvent::FileCompleted { file_name: file_name.clone(), index, total_files, outcome: FileOutcome::Hashed });
+            match previous {
+                // Touched but identical, e.g. copied over with a newer mtime
+                Some(previous) if previous.file_hash == hash && previous.file_size == file_info.len() => {
+                    metadata.artwork_files.push(previous.clone());
+                    summary.unchanged += 1;
+                    summary.touched.push(file_name.clone());
+                    continue;
                 }
+                Some(_) => summary.changed.push(file_name.clone()),
+                None => summary.added.push(file_name.clone()),
             }
+
+            let file_metadata = analyze_file(&path).unwrap_or_else(|_| {
+                crate::file_analyzer::FileMetadata {
+                    format: path.extension().unwrap_or_default().to_string_lossy().to_string().to_uppercase(),
+                }
+            });
+
+            // Keep recording CIDs when the metadata already has them
+            let ipfs_cid = if self.compute_ipfs_cid || previous.is_some_and(|previous| previous.ipfs_cid.is_some()) {
+                Some(ipfs::file_cid(&path, &self.cancellation)?)
+            } else {
+                None
+            };
+
+            metadata.artwork_files.push(ArtworkFile {
+                path: format!("./{}", file_name),
+                file_name,
+                file_hash: hash,
+                file_size: file_info.len(),
+                format: file_metadata.format,
+                ipfs_cid,
+            });
         }
 
-        // Hash certificate if it exists
-        if let Some(certificate_path) = &output_metadata.certificate_of_authenticity {
+        // Pick up a certificate added since the metadata was generated, and re-hash the recorded one
+        if metadata.certificate_of_authenticity.is_none() {
+            metadata.certificate_of_authenticity = detect_certificate_of_authenticity(folder_path);
+        }
+        if let Some(certificate_path) = &metadata.certificate_of_authenticity {
             let certificate_full_path = folder_path.join(certificate_path.trim_start_matches("./"));
             if certificate_full_path.exists() {
                 match self.hasher.hash_file(&certificate_full_path.to_string_lossy()) {
                     Ok(certificate_hash) => {
-                        output_metadata.certificate_hash = Some(certificate_hash);
-                        
-                        // Report certificate hashing
-                        match &self.progress_callback {
-                            ProgressCallback::Cli(callback) => {
-                                callback(format!("Certificate hashed: {}", certificate_path));
-                            }
-                            ProgressCallback::Gui(callback) => {
-                                callback("Certificate".to_string(), 1.0, 1.0);
-                            }
-                            ProgressCallback::None => {}
-                        }
-                    }
-                    Err(e) => {
-                        // Log error but continue
-                        match &self.progress_callback {
-                            ProgressCallback::Cli(callback) => {
-                                callback(format!("Warning: Could not hash certificate: {}", e));
-                            }
-                            ProgressCallback::Gui(callback) => {
-                                callback("Certificate error".to_string(), 1.0, 1.0);
-                            }
-                            ProgressCallback::None => {}
+                        if metadata.certificate_hash.as_ref() != Some(&certificate_hash) {
+                            metadata.certificate_hash = Some(certificate_hash);
+                            summary.certificate_changed = true;
                         }
+                        self.emit(ProgressEvent::CertificateChecked {
+                            certificate_path: certificate_path.clone(),
+                            outcome: CertificateOutcome::Hashed,
+                            error: None,
+                        });
                     }
+                    Err(Error::Cancelled) => return Err(Error::Cancelled),
+                    // Keep the recorded hash and carry on, as generation does
+                    Err(e) => self.emit(ProgressEvent::CertificateChecked {
+                        certificate_path: certificate_path.clone(),
+                        outcome: CertificateOutcome::Failed,
+                        error: Some(e.message()),
+                    }),
                 }
             }
         }
 
-        // Save metadata to file
-        let file_name = format!("{}_metadata.json", metadata.artwork_title.replace(' ', "_"));
-        let output = folder_path.join(file_name);
-        let file = File::create(&output)?;
-        let mut writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(&mut writer, &output_metadata)?;
-        writer.flush()?;
+        self.hasher.save_cache()?;
+        self.emit(ProgressEvent::Finished { processed_files: total_files, failed_files: 0 });
+        if summary.has_changes() {
+            metadata.merkle_root = merkle_root_for_files(&metadata.artwork_files);
+            // The recorded root CID covered the old files
+            metadata.ipfs_root_cid = None;
+        } else if summary.touched.is_empty() {
+            return Ok(summary);
+        }
 
-        Ok(output)
+        write_metadata(metadata_path, &metadata)?;
+
+        Ok(summary)
     }
 
     /// Fingerprints the metadata file itself using BLAKE3
-    pub fn fingerprint_metadata_file(&self, metadata_path: &PathBuf) -> std::io::Result<String> {
-        let file = File::open(metadata_path)?;
-        let mmap = unsafe { memmap::Mmap::map(&file)? };
-        let mut hasher = blake3::Hasher::new();
-        hasher.update(&mmap);
-        let hash = hasher.finalize();
-        Ok(hash.to_hex().to_string())
+    pub fn fingerprint_metadata_file(&self, metadata_path: &Path) -> Result<String> {
+        self.hasher.hash_file(&metadata_path.to_string_lossy())
     }
 
     /// Verifies all files in a metadata file against their recorded hashes
+    /// Same pass as `verify_metadata_file_with_progress`; events go to the observer, which does nothing
+    /// unless one was set with `with_observer`
     pub fn verify_metadata_file(
         &self,
-        metadata_path: &PathBuf,
-        base_folder: &PathBuf,
-    ) -> std::io::Result<VerificationReport> {
+        metadata_path: &Path,
+        base_folder: &Path,
+    ) -> Result<VerificationReport> {
+        self.verify_metadata_file_with_progress(metadata_path, base_folder)
+    }
+
+    /// Verifies metadata file with progress reporting
+    pub fn verify_metadata_file_with_progress(
+        &self,
+        metadata_path: &Path,
+        base_folder: &Path,
+    ) -> Result<VerificationReport> {
+        self.verify_with_checkpoint(metadata_path, base_folder, self.checkpoint)
+    }
+
+    /// Verification pass behind `verify_metadata_file_with_progress`, with the checkpoint setting
+    /// overridden for callers that must not write next to the metadata file, e.g. inside a bag payload
+    pub(crate) fn verify_with_checkpoint(
+        &self,
+        metadata_path: &Path,
+        base_folder: &Path,
+        use_checkpoint: bool,
+    ) -> Result<VerificationReport> {
+        // An archive base is read by streaming its members instead of opening files on disk
+        if archive::is_archive(base_folder) {
+            return self.verify_metadata_file_against_archive(metadata_path, base_folder);
+        }
+
         // Read and parse the metadata file
-        let metadata_content = fs::read_to_string(metadata_path)?;
-        let metadata: Metadata = serde_json::from_str(&metadata_content)?;
+        let metadata = read_metadata_file(metadata_path)?;
 
         // Fingerprint the metadata file itself
         let metadata_file_hash = self.fingerprint_metadata_file(metadata_path)?;
@@ -410,84 +686,133 @@ impl MetadataGenerator {
         let mut results = Vec::new();
         let mut valid_files = 0;
         let mut invalid_files = 0;
+        let mut observed_leaves = Vec::new();
+        let total_files = metadata.artwork_files.len();
+        self.emit(ProgressEvent::ScanStarted { total_files });
+
+        // Pick up the files an interrupted run already read
+        let mut checkpoint = self.open_checkpoint(use_checkpoint, metadata_path, &metadata_file_hash, base_folder, total_files);
+        let mut last_saved = Instant::now();
 
-        // Verify each file
-        for artwork_file in &metadata.artwork_files {
+        // Verify each file with progress reporting
+        for (index, artwork_file) in metadata.artwork_files.iter().enumerate() {
             let file_path = base_folder.join(&artwork_file.file_name);
-            
-            let verification_result = if file_path.exists() {
-                // Hash the actual file
-                match self.hasher.hash_file(&file_path.to_string_lossy()) {
-                    Ok(actual_hash) => {
-                        let is_valid = actual_hash == artwork_file.file_hash;
-                        if is_valid {
-                            valid_files += 1;
-                        } else {
-                            invalid_files += 1;
-                        }
-                        
-                        VerificationResult {
-                            file_name: artwork_file.file_name.clone(),
-                            expected_hash: artwork_file.file_hash.clone(),
-                            actual_hash,
-                            is_valid,
-                            error: None,
-                        }
-                    }
-                    Err(e) => {
+            if self.cancellation.is_cancelled() {
+                save_checkpoint(&checkpoint)?;
+                return Err(Error::Cancelled);
+            }
+            let file_name = &artwork_file.file_name;
+            self.emit(ProgressEvent::FileStarted { file_name: file_name.clone(), index, total_files });
+
+            let resumed_hash = checkpoint
+                .as_ref()
+                .and_then(|(_, checkpoint)| checkpoint.completed_hash(file_name, &file_path));
+            let resumed = resumed_hash.is_some();
+            let actual_hash = if let Some(resumed_hash) = resumed_hash {
+                Ok(resumed_hash)
+            } else if file_path.exists() {
+                self.hash_with_retries(ChecksumAlgorithm::Blake3, &file_path, file_name, index, total_files)
+            } else {
+                Err(Error::io(&file_path, std::io::Error::new(std::io::ErrorKind::NotFound, "File not found")))
+            };
+
+            let verification_result = match actual_hash {
+                Ok(actual_hash) => {
+                    let is_valid = actual_hash == artwork_file.file_hash;
+                    observed_leaves.push(MerkleLeaf {
+                        path: artwork_file.path.clone(),
+                        file_hash: actual_hash.clone(),
+                        file_size: file_path.metadata().map(|m| m.len()).unwrap_or(0),
+                    });
+                    if is_valid {
+                        valid_files += 1;
+                    } else {
                         invalid_files += 1;
-                        VerificationResult {
-                            file_name: artwork_file.file_name.clone(),
-                            expected_hash: artwork_file.file_hash.clone(),
-                            actual_hash: String::new(),
-                            is_valid: false,
-                            error: Some(e.to_string()),
-                        }
+                    }
+                    let outcome = if is_valid { FileOutcome::Valid } else { FileOutcome::Invalid };
+                    self.emit(ProgressEvent::FileCompleted { file_name: file_name.clone(), index, total_files, outcome });
+
+                    if !resumed {
+                        record_in_checkpoint(&mut checkpoint, &mut last_saved, file_name, &file_path, &actual_hash)?;
+                    }
+
+                    VerificationResult {
+                        file_name: file_name.clone(),
+                        expected_hash: artwork_file.file_hash.clone(),
+                        actual_hash,
+                        is_valid,
+                        error: None,
                     }
                 }
-            } else {
-                invalid_files += 1;
-                VerificationResult {
-                    file_name: artwork_file.file_name.clone(),
-                    expected_hash: artwork_file.file_hash.clone(),
-                    actual_hash: String::new(),
-                    is_valid: false,
-                    error: Some("File not found".to_string()),
+                Err(Error::Cancelled) => {
+                    save_checkpoint(&checkpoint)?;
+                    return Err(Error::Cancelled);
+                }
+                Err(e) => {
+                    invalid_files += 1;
+                    self.emit(ProgressEvent::FileFailed { file_name: file_name.clone(), index, total_files, error: e.message() });
+
+                    VerificationResult {
+                        file_name: file_name.clone(),
+                        expected_hash: artwork_file.file_hash.clone(),
+                        actual_hash: String::new(),
+                        is_valid: false,
+                        error: Some(e.message()),
+                    }
                 }
             };
             
             results.push(verification_result);
         }
 
-        let total_files = results.len();
-        
         // Verify certificate if it exists
         let mut certificate_valid = None;
         let mut certificate_hash = None;
         
         if let Some(certificate_path) = &metadata.certificate_of_authenticity {
             let certificate_full_path = base_folder.join(certificate_path.trim_start_matches("./"));
-            if certificate_full_path.exists() {
+            let (outcome, error) = if certificate_full_path.exists() {
                 match self.hasher.hash_file(&certificate_full_path.to_string_lossy()) {
+                    // A certificate without a recorded hash cannot be valid
                     Ok(actual_certificate_hash) => {
-                        certificate_hash = Some(actual_certificate_hash.clone());
-                        if let Some(expected_certificate_hash) = &metadata.certificate_hash {
-                            certificate_valid = Some(actual_certificate_hash == *expected_certificate_hash);
+                        let is_valid = metadata.certificate_hash.as_ref() == Some(&actual_certificate_hash);
+                        certificate_hash = Some(actual_certificate_hash);
+                        if is_valid {
+                            (CertificateOutcome::Valid, None)
                         } else {
-                            certificate_valid = Some(false); // No expected hash recorded
+                            (CertificateOutcome::Invalid, None)
                         }
                     }
-                    Err(_) => {
-                        certificate_valid = Some(false); // Could not hash certificate
+                    Err(Error::Cancelled) => {
+                        save_checkpoint(&checkpoint)?;
+                        return Err(Error::Cancelled);
                     }
+                    Err(e) => (CertificateOutcome::Failed, Some(e.message())),
                 }
             } else {
-                certificate_valid = Some(false); // Certificate file not found
-            }
+                (CertificateOutcome::Missing, None)
+            };
+            certificate_valid = Some(outcome == CertificateOutcome::Valid);
+            self.emit(ProgressEvent::CertificateChecked { certificate_path: certificate_path.clone(), outcome, error });
         }
         
-        // Overall validity includes certificate validity
-        let overall_valid = invalid_files == 0 && certificate_valid.unwrap_or(true);
+        // Recompute the Merkle root from what is actually on disk
+        let merkle_root_valid = metadata.merkle_root.as_ref().map(|expected_root| {
+            observed_leaves.len() == metadata.artwork_files.len()
+                && merkle_root(&observed_leaves).as_ref() == Some(expected_root)
+        });
+
+        // Overall validity includes certificate and Merkle root validity
+        let overall_valid = invalid_files == 0
+            && certificate_valid.unwrap_or(true)
+            && merkle_root_valid.unwrap_or(true);
+
+        // The run completed, so the next one starts from scratch
+        if let Some((checkpoint_path, _)) = &checkpoint {
+            VerificationCheckpoint::remove(checkpoint_path)?;
+        }
+
+        self.emit(ProgressEvent::Finished { processed_files: total_files, failed_files: invalid_files });
 
         Ok(VerificationReport {
             metadata_file_hash,
@@ -498,215 +823,162 @@ impl MetadataGenerator {
             metadata_file_valid: true, // We successfully read it, so it's valid
             certificate_valid,
             certificate_hash,
+            merkle_root_valid,
             overall_valid,
         })
     }
 
-    /// Verifies metadata file with progress reporting
-    pub fn verify_metadata_file_with_progress(
-        &self,
-        metadata_path: &PathBuf,
-        base_folder: &PathBuf,
-    ) -> std::io::Result<VerificationReport> {
-        // Read and parse the metadata file
-        let metadata_content = fs::read_to_string(metadata_path)?;
-        let metadata: Metadata = serde_json::from_str(&metadata_content)?;
+    /// Guards a verified folder: checks it once, then re-hashes artwork files and the certificate as
+    /// filesystem notifications report them changed, emitting `FileChanged` until cancelled
+    /// Returns Ok once cancelled, since stopping is the normal way for a watch to end
+    pub fn watch_metadata_file(&self, metadata_path: &Path, base_folder: &Path) -> Result<()> {
+        if archive::is_archive(base_folder) {
+            return Err(Error::validation(base_folder, "Watch mode needs the folder of artwork files, not an archive"));
+        }
+        let metadata = read_metadata_file(metadata_path)?;
 
-        // Fingerprint the metadata file itself
-        let metadata_file_hash = self.fingerprint_metadata_file(metadata_path)?;
+        // Subscribe before the first check so nothing that changes during it is missed
+        let mut watcher = FolderWatcher::new(base_folder)?;
+        let report = match self.verify_metadata_file_with_progress(metadata_path, base_folder) {
+            Err(Error::Cancelled) => return Ok(()),
+            report => report?,
+        };
+
+        // Last state of each guarded file by absolute path; only a different state is reported, so saving
+        // a file without changing it or a repeated notification raises no alert
+        let mut guarded: HashMap<PathBuf, GuardedFile> = HashMap::new();
+        for (artwork_file, result) in metadata.artwork_files.iter().zip(&report.results) {
+            let path = watcher.folder().join(&artwork_file.file_name);
+            let last_seen = match &result.error {
+                None => GuardedFile::seen(&artwork_file.file_hash, Some(result.actual_hash.clone())),
+                Some(_) if !path.exists() => (FileChange::Removed, None),
+                Some(_) => (FileChange::Failed, None),
+            };
+            guarded.insert(path, GuardedFile {
+                file_name: artwork_file.file_name.clone(),
+                expected_hash: artwork_file.file_hash.clone(),
+                last_seen,
+            });
+        }
+        if let (Some(certificate_path), Some(certificate_hash)) = (&metadata.certificate_of_authenticity, &metadata.certificate_hash) {
+            guarded.insert(watcher.folder().join(certificate_path.trim_start_matches("./")), GuardedFile {
+                file_name: certificate_path.clone(),
+                expected_hash: certificate_hash.clone(),
+                last_seen: GuardedFile::seen(certificate_hash, report.certificate_hash.clone()),
+            });
+        }
+        self.emit(ProgressEvent::WatchStarted {
+            folder: watcher.folder().display().to_string(),
+            total_files: guarded.len(),
+        });
+
+        while !self.cancellation.is_cancelled() {
+            for path in watcher.settled_changes(WATCH_POLL_INTERVAL)? {
+                let Some(guarded_file) = guarded.get_mut(&path) else {
+                    continue;
+                };
+                let (seen, error) = if !path.exists() {
+                    ((FileChange::Removed, None), None)
+                } else {
+                    match self.hasher.hash_file(&path.to_string_lossy()) {
+                        Ok(hash) => (GuardedFile::seen(&guarded_file.expected_hash, Some(hash)), None),
+                        Err(Error::Cancelled) => return Ok(()),
+                        Err(e) => ((FileChange::Failed, None), Some(e.message())),
+                    }
+                };
+                if seen == guarded_file.last_seen {
+                    continue;
+                }
+                let change = seen.0;
+                guarded_file.last_seen = seen;
+                self.emit(ProgressEvent::FileChanged { file_name: guarded_file.file_name.clone(), change, error });
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies a folder against a GNU or BSD-style checksum manifest (e.g. `SHA256SUMS`) instead of metadata JSON
+    /// Files are read with the same retries and checkpoint as metadata verification
+    pub fn verify_checksum_manifest(
+        &self,
+        manifest_path: &Path,
+        base_folder: &Path,
+    ) -> Result<VerificationReport> {
+        let entries = checksums::read_manifest(manifest_path)?;
+        let metadata_file_hash = self.fingerprint_metadata_file(manifest_path)?;
 
         let mut results = Vec::new();
         let mut valid_files = 0;
         let mut invalid_files = 0;
-        let total_files = metadata.artwork_files.len();
+        let total_files = entries.len();
+        self.emit(ProgressEvent::ScanStarted { total_files });
 
-        // Verify each file with progress reporting
-        for (index, artwork_file) in metadata.artwork_files.iter().enumerate() {
-            let file_path = base_folder.join(&artwork_file.file_name);
-            
-            // Report progress
-            match &self.progress_callback {
-                ProgressCallback::Cli(callback) => {
-                    callback(format!("Verifying file: {} ({}/{})", artwork_file.file_name, index + 1, total_files));
-                }
-                ProgressCallback::Gui(callback) => {
-                    let overall_progress = index as f32 / total_files as f32;
-                    callback(artwork_file.file_name.clone(), 0.0, overall_progress);
-                }
-                ProgressCallback::None => {}
+        let mut checkpoint = self.open_checkpoint(self.checkpoint, manifest_path, &metadata_file_hash, base_folder, total_files);
+        let mut last_saved = Instant::now();
+
+        for (index, entry) in entries.iter().enumerate() {
+            let file_path = base_folder.join(&entry.path);
+            if self.cancellation.is_cancelled() {
+                save_checkpoint(&checkpoint)?;
+                return Err(Error::Cancelled);
             }
-            
-            let verification_result = if file_path.exists() {
-                // Hash the actual file
-                match self.hasher.hash_file(&file_path.to_string_lossy()) {
-                    Ok(actual_hash) => {
-                        let is_valid = actual_hash == artwork_file.file_hash;
-                        if is_valid {
-                            valid_files += 1;
-                        } else {
-                            invalid_files += 1;
-                        }
-                        
-                        // Report completion
-                        match &self.progress_callback {
-                            ProgressCallback::Cli(callback) => {
-                                callback(format!("Verified: {} - {}", 
-                                    artwork_file.file_name, 
-                                    if is_valid { "VALID" } else { "INVALID" }
-                                ));
-                            }
-                            ProgressCallback::Gui(callback) => {
-                                let overall_progress = (index + 1) as f32 / total_files as f32;
-                                callback(artwork_file.file_name.clone(), 1.0, overall_progress);
-                            }
-                            ProgressCallback::None => {}
-                        }
-                        
-                        VerificationResult {
-                            file_name: artwork_file.file_name.clone(),
-                            expected_hash: artwork_file.file_hash.clone(),
-                            actual_hash,
-                            is_valid,
-                            error: None,
-                        }
-                    }
-                    Err(e) => {
-                        invalid_files += 1;
-                        
-                        // Report error
-                        match &self.progress_callback {
-                            ProgressCallback::Cli(callback) => {
-                                callback(format!("Error verifying: {} - {}", artwork_file.file_name, e));
-                            }
-                            ProgressCallback::Gui(callback) => {
-                                let overall_progress = (index + 1) as f32 / total_files as f32;
-                                callback(artwork_file.file_name.clone(), 1.0, overall_progress);
-                            }
-                            ProgressCallback::None => {}
-                        }
-                        
-                        VerificationResult {
-                            file_name: artwork_file.file_name.clone(),
-                            expected_hash: artwork_file.file_hash.clone(),
-                            actual_hash: String::new(),
-                            is_valid: false,
-                            error: Some(e.to_string()),
-                        }
-                    }
-                }
+            self.emit(ProgressEvent::FileStarted { file_name: entry.path.clone(), index, total_files });
+
+            let resumed_hash = checkpoint
+                .as_ref()
+                .and_then(|(_, checkpoint)| checkpoint.completed_hash(&entry.path, &file_path));
+            let resumed = resumed_hash.is_some();
+            let actual_hash = if let Some(resumed_hash) = resumed_hash {
+                Ok(resumed_hash)
+            } else if file_path.exists() {
+                self.hash_with_retries(entry.algorithm, &file_path, &entry.path, index, total_files)
             } else {
-                invalid_files += 1;
-                
-                // Report missing file
-                match &self.progress_callback {
-                    ProgressCallback::Cli(callback) => {
-                        callback(format!("Missing file: {}", artwork_file.file_name));
-                    }
-                    ProgressCallback::Gui(callback) => {
-                        let overall_progress = (index + 1) as f32 / total_files as f32;
-                        callback(artwork_file.file_name.clone(), 1.0, overall_progress);
-                    }
-                    ProgressCallback::None => {}
+                Err(Error::io(&file_path, std::io::Error::new(std::io::ErrorKind::NotFound, "File not found")))
+            };
+            if let (Ok(actual_hash), false) = (&actual_hash, resumed) {
+                record_in_checkpoint(&mut checkpoint, &mut last_saved, &entry.path, &file_path, actual_hash)?;
+            }
+
+            let result = match actual_hash {
+                Err(Error::Cancelled) => {
+                    save_checkpoint(&checkpoint)?;
+                    return Err(Error::Cancelled);
                 }
-                
-                VerificationResult {
-                    file_name: artwork_file.file_name.clone(),
-                    expected_hash: artwork_file.file_hash.clone(),
+                Ok(actual_hash) => VerificationResult {
+                    file_name: entry.path.clone(),
+                    expected_hash: entry.hash.clone(),
+                    is_valid: actual_hash == entry.hash,
+                    actual_hash,
+                    error: None,
+                },
+                Err(e) => VerificationResult {
+                    file_name: entry.path.clone(),
+                    expected_hash: entry.hash.clone(),
                     actual_hash: String::new(),
                     is_valid: false,
-                    error: Some("File not found".to_string()),
-                }
+                    error: Some(e.message()),
+                },
             };
-            
-            results.push(verification_result);
-        }
-
-        // Verify certificate if it exists
-        let mut certificate_valid = None;
-        let mut certificate_hash = None;
-        
-        if let Some(certificate_path) = &metadata.certificate_of_authenticity {
-            let certificate_full_path = base_folder.join(certificate_path.trim_start_matches("./"));
-            
-            // Report certificate verification
-            match &self.progress_callback {
-                ProgressCallback::Cli(callback) => {
-                    callback(format!("Verifying certificate: {}", certificate_path));
-                }
-                ProgressCallback::Gui(callback) => {
-                    callback("Certificate".to_string(), 0.0, 1.0);
-                }
-                ProgressCallback::None => {}
-            }
-            
-            if certificate_full_path.exists() {
-                match self.hasher.hash_file(&certificate_full_path.to_string_lossy()) {
-                    Ok(actual_certificate_hash) => {
-                        certificate_hash = Some(actual_certificate_hash.clone());
-                        if let Some(expected_certificate_hash) = &metadata.certificate_hash {
-                            certificate_valid = Some(actual_certificate_hash == *expected_certificate_hash);
-                            
-                            // Report certificate result
-                            match &self.progress_callback {
-                                ProgressCallback::Cli(callback) => {
-                                    callback(format!("Certificate verified: {} - {}", 
-                                        certificate_path, 
-                                        if certificate_valid.unwrap() { "VALID" } else { "INVALID" }
-                                    ));
-                                }
-                                ProgressCallback::Gui(callback) => {
-                                    callback("Certificate".to_string(), 1.0, 1.0);
-                                }
-                                ProgressCallback::None => {}
-                            }
-                        } else {
-                            certificate_valid = Some(false); // No expected hash recorded
-                            
-                            // Report missing expected hash
-                            match &self.progress_callback {
-                                ProgressCallback::Cli(callback) => {
-                                    callback(format!("Certificate error: No expected hash recorded for {}", certificate_path));
-                                }
-                                ProgressCallback::Gui(callback) => {
-                                    callback("Certificate".to_string(), 1.0, 1.0);
-                                }
-                                ProgressCallback::None => {}
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        certificate_valid = Some(false); // Could not hash certificate
-                        
-                        // Report certificate error
-                        match &self.progress_callback {
-                            ProgressCallback::Cli(callback) => {
-                                callback(format!("Certificate error: Could not hash {} - {}", certificate_path, e));
-                            }
-                            ProgressCallback::Gui(callback) => {
-                                callback("Certificate".to_string(), 1.0, 1.0);
-                            }
-                            ProgressCallback::None => {}
-                        }
-                    }
-                }
+            if result.is_valid {
+                valid_files += 1;
             } else {
-                certificate_valid = Some(false); // Certificate file not found
-                
-                // Report missing certificate
-                match &self.progress_callback {
-                    ProgressCallback::Cli(callback) => {
-                        callback(format!("Certificate not found: {}", certificate_path));
-                    }
-                    ProgressCallback::Gui(callback) => {
-                        callback("Certificate".to_string(), 1.0, 1.0);
-                    }
-                    ProgressCallback::None => {}
+                invalid_files += 1;
+            }
+
+            match &result.error {
+                Some(error) => self.emit(ProgressEvent::FileFailed { file_name: entry.path.clone(), index, total_files, error: error.clone() }),
+                None => {
+                    let outcome = if result.is_valid { FileOutcome::Valid } else { FileOutcome::Invalid };
+                    self.emit(ProgressEvent::FileCompleted { file_name: entry.path.clone(), index, total_files, outcome });
                 }
             }
+
+            results.push(result);
         }
-        
-        // Overall validity includes certificate validity
-        let overall_valid = invalid_files == 0 && certificate_valid.unwrap_or(true);
+        if let Some((checkpoint_path, _)) = &checkpoint {
+            VerificationCheckpoint::remove(checkpoint_path)?;
+        }
+        self.emit(ProgressEvent::Finished { processed_files: total_files, failed_files: invalid_files });
 
         Ok(VerificationReport {
             metadata_file_hash,
@@ -714,10 +986,255 @@ impl MetadataGenerator {
             valid_files,
             invalid_files,
             results,
-            metadata_file_valid: true, // We successfully read it, so it's valid
-            certificate_valid,
-            certificate_hash,
-            overall_valid,
+            metadata_file_valid: true,
+            certificate_valid: None,
+            certificate_hash: None,
+            merkle_root_valid: None,
+            overall_valid: invalid_files == 0,
+        })
+    }
+
+    /// Verifies a packaged edition (ZIP or tar.zst) against the metadata JSON embedded in it,
+    /// hashing each entry as it streams out of the archive without extracting to disk
+    pub fn verify_archive(&self, archive_path: &Path) -> Result<VerificationReport> {
+        let contents = self.read_archive_with_progress(archive_path)?;
+        let (metadata_name, metadata_bytes) = contents
+            .metadata
+            .as_ref()
+            .ok_or_else(|| Error::validation(archive_path, "Archive does not contain a metadata JSON file"))?;
+        let metadata: Metadata = serde_json::from_slice(metadata_bytes)
+            .map_err(|e| Error::parse(archive_path, format!("{}: {}", metadata_name, e)))?;
+        let metadata_file_hash = blake3::hash(metadata_bytes).to_hex().to_string();
+
+        let mut report = verify_against_entries(&metadata, metadata_file_hash, &contents);
+
+        // Anything else in the archive is not covered by the embedded manifest
+        let mut listed: HashSet<&str> = metadata
+            .artwork_files
+            .iter()
+            .filter_map(|file| contents.resolve(&file.path).or_else(|| contents.resolve(&file.file_name)))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        listed.insert(metadata_name);
+        if let Some((name, _)) = metadata.certificate_of_authenticity.as_ref().and_then(|path| contents.resolve(path)) {
+            listed.insert(name);
+        }
+        for name in contents.entries.keys().filter(|name| !listed.contains(name.as_str())) {
+            report.results.push(VerificationResult {
+                file_name: name.clone(),
+                expected_hash: String::new(),
+                actual_hash: contents.entries[name].file_hash.clone(),
+                is_valid: false,
+                error: Some("Not listed in the embedded metadata".to_string()),
+            });
+            report.invalid_files += 1;
+            report.total_files += 1;
+            report.overall_valid = false;
+        }
+
+        self.emit_outcomes(&report);
+        Ok(report)
+    }
+
+    /// Verifies an external metadata file against the members of a ZIP or TAR archive
+    fn verify_metadata_file_against_archive(
+        &self,
+        metadata_path: &Path,
+        archive_path: &Path,
+    ) -> Result<VerificationReport> {
+        let metadata = read_metadata_file(metadata_path)?;
+        let metadata_file_hash = self.fingerprint_metadata_file(metadata_path)?;
+
+        let contents = self.read_archive_with_progress(archive_path)?;
+        let report = verify_against_entries(&metadata, metadata_file_hash, &contents);
+        self.emit_outcomes(&report);
+        Ok(report)
+    }
+
+    /// Entries are reported as they stream past; byte progress counts the archive file itself
+    /// The archive is one stream, so a transient read error starts it again from the beginning
+    fn read_archive_with_progress(&self, archive_path: &Path) -> Result<ArchiveContents> {
+        let archive_name = archive_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        self.emit(ProgressEvent::ScanStarted { total_files: 0 });
+        self.with_retries(archive_path, &archive_name, 0, 0, || {
+            let mut index = 0;
+            archive::read_archive(archive_path, &self.cancellation, &mut |entry_name, bytes_read, archive_size| {
+                if !entry_name.is_empty() {
+                    self.emit(ProgressEvent::FileStarted { file_name: entry_name.to_string(), index, total_files: 0 });
+                    index += 1;
+                }
+                self.emit(ProgressEvent::BytesHashed {
+                    file_name: archive_name.clone(),
+                    bytes_hashed: bytes_read,
+                    file_size: archive_size,
+                    index,
+                    total_files: 0,
+                });
+            })
         })
     }
-} 
\ No newline at end of file
+
+    /// Reports the outcome of each file once an archive has been compared against its metadata
+    fn emit_outcomes(&self, report: &VerificationReport) {
+        let total_files = report.results.len();
+        for (index, result) in report.results.iter().enumerate() {
+            let file_name = result.file_name.clone();
+            match &result.error {
+                Some(error) => self.emit(ProgressEvent::FileFailed { file_name, index, total_files, error: error.clone() }),
+                None => {
+                    let outcome = if result.is_valid { FileOutcome::Valid } else { FileOutcome::Invalid };
+                    self.emit(ProgressEvent::FileCompleted { file_name, index, total_files, outcome });
+                }
+            }
+        }
+        self.emit(ProgressEvent::Finished { processed_files: total_files, failed_files: report.invalid_files });
+    }
+}
+
+/// File read through `MetadataGenerator::progress_reader`, reporting progress about every 1 MiB
+pub(crate) struct ProgressReader<'a> {
+    generator: &'a MetadataGenerator,
+    file: File,
+    file_name: &'a str,
+    index: usize,
+    total_files: usize,
+    file_size: u64,
+    bytes_read: u64,
+    bytes_reported: u64,
+}
+
+impl Read for ProgressReader<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.generator.cancellation.check()?;
+        let read = self.file.read(buffer)?;
+        self.bytes_read += read as u64;
+        if self.bytes_read - self.bytes_reported >= PROGRESS_STEP as u64 || (read == 0 && self.bytes_read > self.bytes_reported) {
+            self.generator.emit(ProgressEvent::BytesHashed {
+                file_name: self.file_name.to_string(),
+                bytes_hashed: self.bytes_read,
+                file_size: self.file_size,
+                index: self.index,
+                total_files: self.total_files,
+            });
+            self.bytes_reported = self.bytes_read;
+        }
+        Ok(read)
+    }
+}
+
+/// Reads and parses a metadata JSON file
+pub fn read_metadata_file(metadata_path: &Path) -> Result<Metadata> {
+    let metadata_content = fs::read_to_string(metadata_path).with_path(metadata_path)?;
+    serde_json::from_str(&metadata_content).map_err(|e| Error::parse(metadata_path, e))
+}
+
+/// Writes the checkpoint of a resumable verification, if one is kept
+fn save_checkpoint(checkpoint: &Option<(PathBuf, VerificationCheckpoint)>) -> Result<()> {
+    match checkpoint {
+        Some((checkpoint_path, checkpoint)) => checkpoint.save(checkpoint_path),
+        None => Ok(()),
+    }
+}
+
+/// Records a file that was read completely, saving the checkpoint every `CHECKPOINT_INTERVAL`
+fn record_in_checkpoint(
+    checkpoint: &mut Option<(PathBuf, VerificationCheckpoint)>,
+    last_saved: &mut Instant,
+    file_name: &str,
+    file_path: &Path,
+    file_hash: &str,
+) -> Result<()> {
+    if let Some((checkpoint_path, checkpoint)) = checkpoint {
+        checkpoint.record(file_name, file_path, file_hash.to_string());
+        if last_saved.elapsed() >= CHECKPOINT_INTERVAL {
+            checkpoint.save(checkpoint_path)?;
+            *last_saved = Instant::now();
+        }
+    }
+    Ok(())
+}
+
+/// Writes metadata as pretty-printed JSON
+pub(crate) fn write_metadata(metadata_path: &Path, metadata: &Metadata) -> Result<()> {
+    let file = File::create(metadata_path).with_path(metadata_path)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, metadata).map_err(|e| Error::io(metadata_path, e.into()))?;
+    writer.flush().with_path(metadata_path)
+}
+
+/// Compares metadata against BLAKE3 hashes already computed while streaming an archive
+pub fn verify_against_entries(
+    metadata: &Metadata,
+    metadata_file_hash: String,
+    contents: &ArchiveContents,
+) -> VerificationReport {
+    let mut results = Vec::new();
+    let mut observed_leaves = Vec::new();
+
+    for artwork_file in &metadata.artwork_files {
+        let entry = contents
+            .resolve(&artwork_file.path)
+            .or_else(|| contents.resolve(&artwork_file.file_name))
+            .map(|(_, entry)| entry);
+        let result = match entry {
+            Some(entry) => {
+                observed_leaves.push(MerkleLeaf {
+                    path: artwork_file.path.clone(),
+                    file_hash: entry.file_hash.clone(),
+                    file_size: entry.file_size,
+                });
+                VerificationResult {
+                    file_name: artwork_file.file_name.clone(),
+                    expected_hash: artwork_file.file_hash.clone(),
+                    actual_hash: entry.file_hash.clone(),
+                    is_valid: entry.file_hash == artwork_file.file_hash,
+                    error: None,
+                }
+            }
+            None => VerificationResult {
+                file_name: artwork_file.file_name.clone(),
+                expected_hash: artwork_file.file_hash.clone(),
+                actual_hash: String::new(),
+                is_valid: false,
+                error: Some("File not found".to_string()),
+            },
+        };
+        results.push(result);
+    }
+    let valid_files = results.iter().filter(|result| result.is_valid).count();
+    let invalid_files = results.len() - valid_files;
+
+    let mut certificate_valid = None;
+    let mut certificate_hash = None;
+    if let Some(certificate_path) = &metadata.certificate_of_authenticity {
+        match contents.resolve(certificate_path) {
+            Some((_, entry)) => {
+                certificate_hash = Some(entry.file_hash.clone());
+                certificate_valid = Some(metadata.certificate_hash.as_ref() == Some(&entry.file_hash));
+            }
+            None => certificate_valid = Some(false), // Certificate file not found
+        }
+    }
+
+    let merkle_root_valid = metadata.merkle_root.as_ref().map(|expected_root| {
+        observed_leaves.len() == metadata.artwork_files.len()
+            && merkle_root(&observed_leaves).as_ref() == Some(expected_root)
+    });
+
+    let overall_valid = invalid_files == 0
+        && certificate_valid.unwrap_or(true)
+        && merkle_root_valid.unwrap_or(true);
+
+    VerificationReport {
+        metadata_file_hash,
+        total_files: results.len(),
+        valid_files,
+        invalid_files,
+        results,
+        metadata_file_valid: true,
+        certificate_valid,
+        certificate_hash,
+        merkle_root_valid,
+        overall_valid,
+    }
+}
@@ -0,0 +1,222 @@
+use std::path::Path;
+use crate::error::{IoResultExt, Result};
+use crate::metadata_generator::VerificationReport;
+use crate::xml::escape_xml;
+
+/// Machine-readable formats a verification report can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// The full report as pretty-printed JSON
+    Json,
+    /// JUnit XML, understood by CI dashboards
+    #[value(alias = "xml")]
+    Junit,
+    /// Standalone HTML page with inline styles, suitable for sending to a collector
+    #[value(alias = "htm")]
+    Html,
+}
+
+impl ReportFormat {
+    pub const ALL: [ReportFormat; 3] = [ReportFormat::Json, ReportFormat::Junit, ReportFormat::Html];
+
+    /// Parses `json`, `junit` (or `xml`) and `html`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(ReportFormat::Json),
+            "junit" | "xml" => Some(ReportFormat::Junit),
+            "html" | "htm" => Some(ReportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "JSON",
+            ReportFormat::Junit => "JUnit XML",
+            ReportFormat::Html => "HTML",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Junit => "xml",
+            ReportFormat::Html => "html",
+        }
+    }
+
+    /// Picks the format from a report file name, e.g. `report.html`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension().and_then(|extension| Self::from_name(&extension.to_string_lossy()))
+    }
+}
+
+/// Renders a report; `subject` names what was verified, e.g. the metadata file or archive
+pub fn render_report(report: &VerificationReport, format: ReportFormat, subject: &str) -> Result<String> {
+    match format {
+        ReportFormat::Json => Ok(format!("{}\n", serde_json::to_string_pretty(report)?)),
+        ReportFormat::Junit => Ok(junit_report(report, subject)),
+        ReportFormat::Html => Ok(html_report(report, subject)),
+    }
+}
+
+pub fn write_report(report: &VerificationReport, format: ReportFormat, subject: &str, output_path: &Path) -> Result<()> {
+    std::fs::write(output_path, render_report(report, format, subject)?).with_path(output_path)
+}
+
+/// One check in the report: artwork files first, then the certificate and Merkle root when present
+struct Check<'a> {
+    class_name: &'static str,
+    name: &'a str,
+    passed: bool,
+    message: String,
+    details: String,
+}
+
+fn checks(report: &VerificationReport) -> Vec<Check<'_>> {
+    let mut checks: Vec<Check> = report
+        .results
+        .iter()
+        .map(|result| Check {
+            class_name: "artwork_files",
+            name: &result.file_name,
+            passed: result.is_valid,
+            message: result.error.clone().unwrap_or_else(|| "Hash mismatch".to_string()),
+            details: format!("Expected: {}\nActual: {}", result.expected_hash, result.actual_hash),
+        })
+        .collect();
+    if let Some(certificate_valid) = report.certificate_valid {
+        checks.push(Check {
+            class_name: "edition",
+            name: "certificate",
+            passed: certificate_valid,
+            message: "Certificate hash does not match".to_string(),
+            details: format!("Actual: {}", report.certificate_hash.as_deref().unwrap_or("")),
+        });
+    }
+    if let Some(merkle_root_valid) = report.merkle_root_valid {
+        checks.push(Check {
+            class_name: "edition",
+            name: "merkle_root",
+            passed: merkle_root_valid,
+            message: "Merkle root does not match the files".to_string(),
+            details: String::new(),
+        });
+    }
+    checks
+}
+
+fn junit_report(report: &VerificationReport, subject: &str) -> String {
+    let checks = checks(report);
+    let failures = checks.iter().filter(|check| !check.passed).count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"metadata-verification\" tests=\"{}\" failures=\"{}\" errors=\"0\">\n",
+        checks.len(),
+        failures
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\">\n",
+        escape_xml(subject),
+        checks.len(),
+        failures
+    ));
+    xml.push_str("    <properties>\n");
+    xml.push_str(&format!(
+        "      <property name=\"metadata_file_hash\" value=\"{}\"/>\n",
+        escape_xml(&report.metadata_file_hash)
+    ));
+    xml.push_str(&format!("      <property name=\"overall_valid\" value=\"{}\"/>\n", report.overall_valid));
+    xml.push_str("    </properties>\n");
+
+    for check in &checks {
+        let opening = format!(
+            "    <testcase classname=\"{}\" name=\"{}\"",
+            check.class_name,
+            escape_xml(check.name)
+        );
+        if check.passed {
+            xml.push_str(&format!("{}/>\n", opening));
+        } else {
+            xml.push_str(&format!("{}>\n", opening));
+            xml.push_str(&format!(
+                "      <failure message=\"{}\" type=\"verification\">{}</failure>\n",
+                escape_xml(&check.message),
+                escape_xml(&check.details)
+            ));
+            xml.push_str("    </testcase>\n");
+        }
+    }
+
+    xml.push_str("  </testsuite>\n");
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,Helvetica,Arial,sans-serif;margin:2em;color:#222}\
+h1{font-size:1.4em}table{border-collapse:collapse;width:100%;margin-top:1em}\
+th,td{border:1px solid #ccc;padding:6px 8px;text-align:left;vertical-align:top}\
+th{background:#f4f4f4}code{font-size:0.85em;word-break:break-all}\
+.pass{color:#1a7f37}.fail{color:#cf222e}.banner{padding:0.8em 1em;border-radius:6px;font-weight:bold}\
+.banner.pass{background:#dafbe1}.banner.fail{background:#ffebe9}";
+
+fn html_report(report: &VerificationReport, subject: &str) -> String {
+    let status = |passed: bool| if passed { ("pass", "✅ Valid") } else { ("fail", "❌ Invalid") };
+    let (overall_class, overall_text) = if report.overall_valid {
+        ("pass", "✅ All files and certificate are valid")
+    } else {
+        ("fail", "❌ Some files or certificate are invalid")
+    };
+
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>Verification report: {}</title>\n", escape_xml(subject)));
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", HTML_STYLE));
+    html.push_str(&format!("<h1>Verification report</h1>\n<p>{}</p>\n", escape_xml(subject)));
+    html.push_str(&format!("<p class=\"banner {}\">{}</p>\n", overall_class, overall_text));
+
+    html.push_str("<table>\n");
+    html.push_str(&format!(
+        "<tr><th>Metadata file hash</th><td><code>{}</code></td></tr>\n",
+        escape_xml(&report.metadata_file_hash)
+    ));
+    html.push_str(&format!(
+        "<tr><th>Files</th><td>{} total, {} valid, {} invalid</td></tr>\n",
+        report.total_files, report.valid_files, report.invalid_files
+    ));
+    let merkle_root = match report.merkle_root_valid {
+        Some(passed) => format!("<span class=\"{}\">{}</span>", status(passed).0, status(passed).1),
+        None => "No Merkle root recorded".to_string(),
+    };
+    html.push_str(&format!("<tr><th>Merkle root</th><td>{}</td></tr>\n", merkle_root));
+    let certificate = match report.certificate_valid {
+        Some(passed) => format!(
+            "<span class=\"{}\">{}</span> <code>{}</code>",
+            status(passed).0,
+            status(passed).1,
+            escape_xml(report.certificate_hash.as_deref().unwrap_or(""))
+        ),
+        None => "No certificate found".to_string(),
+    };
+    html.push_str(&format!("<tr><th>Certificate</th><td>{}</td></tr>\n", certificate));
+    html.push_str("</table>\n");
+
+    html.push_str("<table>\n<tr><th>File</th><th>Status</th><th>Expected hash</th><th>Actual hash</th></tr>\n");
+    for result in &report.results {
+        let (class, text) = status(result.is_valid);
+        let text = match (&result.error, result.is_valid) {
+            (Some(error), false) => format!("{}: {}", text, escape_xml(error)),
+            _ => text.to_string(),
+        };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td class=\"{}\">{}</td><td><code>{}</code></td><td><code>{}</code></td></tr>\n",
+            escape_xml(&result.file_name),
+            class,
+            text,
+            escape_xml(&result.expected_hash),
+            escape_xml(&result.actual_hash)
+        ));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::file_analyzer::{image_dimensions, media_kind, mime_type, MediaKind};
+use crate::metadata_generator::Metadata;
+
+/// Token metadata following Tezos TZIP-21 (rich metadata for NFTs)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Tzip21Metadata {
+    pub name: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artifact_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_uri: Option<String>,
+    pub creators: Vec<String>,
+    pub rights: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    pub decimals: u8,
+    pub is_boolean_amount: bool,
+    pub should_prefer_symbol: bool,
+    pub tags: Vec<String>,
+    pub formats: Vec<Tzip21Format>,
+    pub attributes: Vec<Tzip21Attribute>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Tzip21Format {
+    pub uri: String,
+    pub mime_type: String,
+    pub file_size: u64,
+    pub file_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<Tzip21Dimensions>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tzip21Dimensions {
+    pub value: String,
+    pub unit: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tzip21Attribute {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Tzip21Options {
+    /// Base URI for asset links, e.g. `ipfs://bafy.../`
+    pub uri_base: Option<String>,
+    /// Folder containing the artwork files, used to read image dimensions
+    pub base_folder: Option<PathBuf>,
+    /// Tezos addresses or names of the creators, defaults to the artwork creator
+    pub creators: Vec<String>,
+    /// Rights statement, defaults to all rights reserved by the creator
+    pub rights: Option<String>,
+}
+
+/// Converts a metadata document and its artwork files into TZIP-21 token metadata
+pub fn to_tzip21(metadata: &Metadata, options: &Tzip21Options) -> Tzip21Metadata {
+    let uri_base = options.uri_base.as_deref();
+
+    let formats: Vec<Tzip21Format> = metadata
+        .artwork_files
+        .iter()
+        .map(|file| {
+            let dimensions = match (&options.base_folder, media_kind(&file.format)) {
+                (Some(base_folder), MediaKind::Image) => image_dimensions(&base_folder.join(&file.file_name)),
+                _ => None,
+            };
+            Tzip21Format {
+                uri: metadata.asset_uri(file, uri_base),
+                mime_type: mime_type(&file.format).to_string(),
+                file_size: file.file_size,
+                file_name: file.file_name.clone(),
+                dimensions: dimensions.map(|(width, height)| Tzip21Dimensions {
+                    value: format!("{}x{}", width, height),
+                    unit: "px".to_string(),
+                }),
+            }
+        })
+        .collect();
+
+    // The artifact is the primary work: time-based or interactive media first, otherwise an image
+    let display_uri = metadata
+        .artwork_files
+        .iter()
+        .find(|file| media_kind(&file.format) == MediaKind::Image)
+        .map(|file| metadata.asset_uri(file, uri_base));
+    let artifact_uri = metadata
+        .artwork_files
+        .iter()
+        .find(|file| {
+            matches!(
+                media_kind(&file.format),
+                MediaKind::Video | MediaKind::Audio | MediaKind::Model | MediaKind::Html
+            )
+        })
+        .map(|file| metadata.asset_uri(file, uri_base))
+        .or_else(|| display_uri.clone())
+        .or_else(|| formats.first().map(|format| format.uri.clone()));
+
+    let creators = if options.creators.is_empty() {
+        vec![metadata.artwork_creator.clone()]
+    } else {
+        options.creators.clone()
+    };
+    let rights = options.rights.clone().unwrap_or_else(|| metadata.default_rights());
+
+    // TZIP-21 dates are ISO 8601 date-times; the issue date is recorded as YYYY-MM-DD
+    let date = if metadata.issue_date.len() == 10 {
+        Some(format!("{}T00:00:00Z", metadata.issue_date))
+    } else {
+        None
+    };
+
+    let mut attributes = vec![
+        Tzip21Attribute {
+            name: "Edition".to_string(),
+            value: format!("{}/{}", metadata.edition_number, metadata.total_editions),
+        },
+        Tzip21Attribute {
+            name: "Year".to_string(),
+            value: metadata.year_of_creation.to_string(),
+        },
+        Tzip21Attribute {
+            name: "Artwork ID".to_string(),
+            value: metadata.artwork_id.clone(),
+        },
+    ];
+    if !metadata.medium.is_empty() {
+        attributes.push(Tzip21Attribute {
+            name: "Medium".to_string(),
+            value: metadata.medium.join(", "),
+        });
+    }
+    if !metadata.gallery.is_empty() {
+        attributes.push(Tzip21Attribute {
+            name: "Gallery".to_string(),
+            value: metadata.gallery.clone(),
+        });
+    }
+
+    let description = if metadata.long_description.is_empty() {
+        metadata.short_description.clone()
+    } else {
+        metadata.long_description.clone()
+    };
+
+    Tzip21Metadata {
+        name: metadata.artwork_title.clone(),
+        description,
+        artifact_uri,
+        thumbnail_uri: display_uri.clone(),
+        display_uri,
+        creators,
+        rights,
+        date,
+        decimals: 0,
+        is_boolean_amount: metadata.total_editions == 1,
+        should_prefer_symbol: false,
+        tags: metadata.keywords.clone(),
+        formats,
+        attributes,
+    }
+}
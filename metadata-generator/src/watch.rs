@@ -0,0 +1,100 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use crate::error::{Error, IoResultExt, Result};
+use crate::events::ProgressEvent;
+use crate::time::utc_now;
+
+/// How long a file must go without further change notifications before it is re-hashed, so a file
+/// that is still being written is hashed once it is complete rather than on every write
+const QUIET_PERIOD: Duration = Duration::from_secs(1);
+
+/// Filesystem change notifications for a folder and everything below it (inotify on Linux,
+/// FSEvents on macOS, ReadDirectoryChangesW on Windows)
+pub struct FolderWatcher {
+    folder: PathBuf,
+    // Dropping the watcher ends the subscription
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<notify::Result<notify::Event>>,
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl FolderWatcher {
+    pub fn new(folder: &Path) -> Result<Self> {
+        let folder = fs::canonicalize(folder).with_path(folder)?;
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(|e| notify_error(&folder, e))?;
+        watcher.watch(&folder, RecursiveMode::Recursive).map_err(|e| notify_error(&folder, e))?;
+        Ok(Self { folder, _watcher: watcher, receiver, pending: HashMap::new() })
+    }
+
+    /// The watched folder as an absolute path; changed paths are reported below it
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Waits up to `timeout` for notifications and returns the paths whose changes have settled
+    pub fn settled_changes(&mut self, timeout: Duration) -> Result<Vec<PathBuf>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.receiver.recv_timeout(remaining) {
+                Ok(Ok(event)) if !event.kind.is_access() => {
+                    for path in event.paths {
+                        self.pending.insert(path, Instant::now());
+                    }
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => return Err(notify_error(&self.folder, e)),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(Error::io(&self.folder, std::io::Error::other("File watcher stopped")));
+                }
+            }
+        }
+
+        let mut settled = Vec::new();
+        self.pending.retain(|path, last_change| {
+            let quiet = last_change.elapsed() >= QUIET_PERIOD;
+            if quiet {
+                settled.push(path.clone());
+            }
+            !quiet
+        });
+        settled.sort();
+        Ok(settled)
+    }
+}
+
+fn notify_error(folder: &Path, error: notify::Error) -> Error {
+    match error.kind {
+        notify::ErrorKind::Io(source) => Error::io(folder, source),
+        _ => Error::io(folder, std::io::Error::other(error.to_string())),
+    }
+}
+
+/// Text log of watch alerts, one timestamped line per alert, for installations nobody is looking at
+pub struct AlertLog {
+    path: PathBuf,
+}
+
+impl AlertLog {
+    pub fn open(path: &Path) -> Self {
+        Self { path: path.to_path_buf() }
+    }
+
+    /// Appends the event if it is an alert or a file being restored; other events are skipped
+    pub fn record(&self, event: &ProgressEvent) -> Result<()> {
+        if !matches!(event, ProgressEvent::FileChanged { .. }) {
+            return Ok(());
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path).with_path(&self.path)?;
+        writeln!(file, "{} {}", utc_now(), event).with_path(&self.path)
+    }
+}
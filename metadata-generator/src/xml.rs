@@ -0,0 +1,8 @@
+/// Escapes text for inclusion in XML, SVG and HTML content and attribute values
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
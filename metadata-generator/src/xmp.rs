@@ -0,0 +1,208 @@
+use std::{collections::HashSet, fs, path::{Path, PathBuf}};
+use crate::error::{IoResultExt, Result};
+use crate::file_analyzer::{media_kind, mime_type, MediaKind};
+use crate::metadata_generator::{ArtworkFile, Metadata};
+use crate::xml::escape_xml;
+
+/// Namespace for edition fields that have no Dublin Core or XMP equivalent
+pub const EDITION_NAMESPACE: &str = "https://github.com/imetandy/Editions_metadata/ns/edition/1.0/";
+
+/// Builds an XMP sidecar packet describing a single artwork file
+pub fn xmp_sidecar(metadata: &Metadata, artwork_file: &ArtworkFile, rights: &str) -> String {
+    let keywords: String = metadata
+        .keywords
+        .iter()
+        .map(|keyword| format!("<rdf:li>{}</rdf:li>", escape_xml(keyword)))
+        .collect();
+    let description = if metadata.long_description.is_empty() {
+        &metadata.short_description
+    } else {
+        &metadata.long_description
+    };
+    let merkle_root = metadata
+        .merkle_root
+        .as_ref()
+        .map(|merkle_root| format!("\n    <edition:MerkleRoot>{}</edition:MerkleRoot>", merkle_root))
+        .unwrap_or_default();
+
+    format!(
+        r#"<?xpacket begin="{bom}" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:xmpRights="http://ns.adobe.com/xap/1.0/rights/"
+    xmlns:edition="{namespace}">
+    <dc:title><rdf:Alt><rdf:li xml:lang="x-default">{title}</rdf:li></rdf:Alt></dc:title>
+    <dc:creator><rdf:Seq><rdf:li>{creator}</rdf:li></rdf:Seq></dc:creator>
+    <dc:rights><rdf:Alt><rdf:li xml:lang="x-default">{rights}</rdf:li></rdf:Alt></dc:rights>
+    <dc:description><rdf:Alt><rdf:li xml:lang="x-default">{description}</rdf:li></rdf:Alt></dc:description>
+    <dc:subject><rdf:Bag>{keywords}</rdf:Bag></dc:subject>
+    <dc:identifier>{artwork_id}</dc:identifier>
+    <dc:format>{format}</dc:format>
+    <xmp:CreateDate>{year}</xmp:CreateDate>
+    <xmpRights:Marked>True</xmpRights:Marked>
+    <edition:ArtworkId>{artwork_id}</edition:ArtworkId>
+    <edition:EditionNumber>{edition_number}</edition:EditionNumber>
+    <edition:TotalEditions>{total_editions}</edition:TotalEditions>
+    <edition:FileName>{file_name}</edition:FileName>
+    <edition:FileSize>{file_size}</edition:FileSize>
+    <edition:ContentHash>{file_hash}</edition:ContentHash>
+    <edition:HashAlgorithm>BLAKE3</edition:HashAlgorithm>{merkle_root}
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>
+"#,
+        bom = '\u{feff}',
+        namespace = EDITION_NAMESPACE,
+        title = escape_xml(&metadata.artwork_title),
+        creator = escape_xml(&metadata.artwork_creator),
+        rights = escape_xml(rights),
+        description = escape_xml(description),
+        keywords = keywords,
+        artwork_id = escape_xml(&metadata.artwork_id),
+        format = mime_type(&artwork_file.format),
+        year = metadata.year_of_creation,
+        edition_number = metadata.edition_number,
+        total_editions = metadata.total_editions,
+        file_name = escape_xml(&artwork_file.file_name),
+        file_size = artwork_file.file_size,
+        file_hash = artwork_file.file_hash,
+        merkle_root = merkle_root,
+    )
+}
+
+/// True for a sidecar written by `write_xmp_sidecars`, recognised by the edition namespace it declares
+/// Sidecars the artist ships as part of the work are artwork files like any other
+pub fn is_generated_sidecar(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("xmp"))
+        && fs::read_to_string(path).is_ok_and(|content| content.contains(EDITION_NAMESPACE))
+}
+
+/// Writes an XMP sidecar for every artwork file into the output folder
+/// Sidecars are named `<stem>.xmp` as Adobe tools expect, or `<file name>.xmp` when two files share a stem
+/// or the short name is taken by an artwork file; XMP files shipped with the work get no sidecar
+pub fn write_xmp_sidecars(metadata: &Metadata, output_folder: &Path, rights: Option<&str>) -> Result<Vec<PathBuf>> {
+    let rights = rights.map(str::to_string).unwrap_or_else(|| metadata.default_rights());
+
+    let mut seen_stems = HashSet::new();
+    let shared_stems: HashSet<String> = metadata
+        .artwork_files
+        .iter()
+        .filter_map(|file| {
+            let stem = Path::new(&file.file_name).file_stem()?.to_string_lossy().to_string();
+            if seen_stems.insert(stem.clone()) { None } else { Some(stem) }
+        })
+        .collect();
+
+    let mut written = Vec::new();
+    for artwork_file in &metadata.artwork_files {
+        if Path::new(&artwork_file.file_name).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("xmp")) {
+            continue;
+        }
+        let stem = Path::new(&artwork_file.file_name)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let stem_taken = metadata.artwork_files.iter().any(|file| file.file_name == format!("{}.xmp", stem));
+        let sidecar_name = if shared_stems.contains(&stem) || stem_taken {
+            format!("{}.xmp", artwork_file.file_name)
+        } else {
+            format!("{}.xmp", stem)
+        };
+        let sidecar_path = output_folder.join(sidecar_name);
+        fs::write(&sidecar_path, xmp_sidecar(metadata, artwork_file, &rights)).with_path(&sidecar_path)?;
+        written.push(sidecar_path);
+    }
+
+    Ok(written)
+}
+
+/// DCMI Type vocabulary term for an artwork file
+fn dcmi_type(artwork_file: &ArtworkFile) -> &'static str {
+    match media_kind(&artwork_file.format) {
+        MediaKind::Image => "StillImage",
+        MediaKind::Video => "MovingImage",
+        MediaKind::Audio => "Sound",
+        MediaKind::Html | MediaKind::Model => "InteractiveResource",
+        MediaKind::Document => "Text",
+        MediaKind::Other => "Dataset",
+    }
+}
+
+/// Builds a Dublin Core XML record describing the whole edition
+pub fn dublin_core_record(metadata: &Metadata, rights: Option<&str>) -> String {
+    let rights = rights.map(str::to_string).unwrap_or_else(|| metadata.default_rights());
+    let mut elements = vec![
+        format!("  <dc:title>{}</dc:title>", escape_xml(&metadata.artwork_title)),
+    ];
+
+    if !metadata.artwork_short_title.is_empty() {
+        elements.push(format!("  <dcterms:alternative>{}</dcterms:alternative>", escape_xml(&metadata.artwork_short_title)));
+    }
+    elements.push(format!("  <dc:creator>{}</dc:creator>", escape_xml(&metadata.artwork_creator)));
+    for keyword in &metadata.keywords {
+        elements.push(format!("  <dc:subject>{}</dc:subject>", escape_xml(keyword)));
+    }
+    if !metadata.short_description.is_empty() {
+        elements.push(format!("  <dcterms:abstract>{}</dcterms:abstract>", escape_xml(&metadata.short_description)));
+    }
+    if !metadata.long_description.is_empty() {
+        elements.push(format!("  <dc:description>{}</dc:description>", escape_xml(&metadata.long_description)));
+    }
+    if !metadata.gallery.is_empty() {
+        elements.push(format!("  <dc:publisher>{}</dc:publisher>", escape_xml(&metadata.gallery)));
+    }
+    elements.push(format!("  <dcterms:created>{}</dcterms:created>", metadata.year_of_creation));
+    if !metadata.issue_date.is_empty() {
+        elements.push(format!("  <dcterms:issued>{}</dcterms:issued>", escape_xml(&metadata.issue_date)));
+    }
+
+    let mut types: Vec<&str> = metadata.artwork_files.iter().map(dcmi_type).collect();
+    types.sort();
+    types.dedup();
+    for dcmi_type in types {
+        elements.push(format!("  <dc:type xsi:type=\"dcterms:DCMIType\">{}</dc:type>", dcmi_type));
+    }
+
+    let mut formats: Vec<&str> = metadata.artwork_files.iter().map(|file| mime_type(&file.format)).collect();
+    formats.sort();
+    formats.dedup();
+    for format in formats {
+        elements.push(format!("  <dc:format xsi:type=\"dcterms:IMT\">{}</dc:format>", format));
+    }
+
+    let total_size: u64 = metadata.artwork_files.iter().map(|file| file.file_size).sum();
+    elements.push(format!("  <dcterms:extent>{} bytes</dcterms:extent>", total_size));
+    for medium in &metadata.medium {
+        elements.push(format!("  <dcterms:medium>{}</dcterms:medium>", escape_xml(medium)));
+    }
+    elements.push(format!("  <dc:identifier>{}</dc:identifier>", escape_xml(&metadata.artwork_id)));
+    elements.push(format!(
+        "  <dcterms:bibliographicCitation>Edition {} of {}</dcterms:bibliographicCitation>",
+        metadata.edition_number, metadata.total_editions
+    ));
+    if let Some(merkle_root) = &metadata.merkle_root {
+        elements.push(format!("  <dc:identifier>urn:blake3-merkle:{}</dc:identifier>", merkle_root));
+    }
+    for artwork_file in &metadata.artwork_files {
+        elements.push(format!(
+            "  <dcterms:hasPart>{} (blake3:{})</dcterms:hasPart>",
+            escape_xml(&artwork_file.file_name),
+            artwork_file.file_hash
+        ));
+    }
+    elements.push(format!("  <dc:rights>{}</dc:rights>", escape_xml(&rights)));
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+{}
+</metadata>
+"#,
+        elements.join("\n")
+    )
+}